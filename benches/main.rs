@@ -1,4 +1,5 @@
-use cepstrum_extractor::{CepstrumExtractor, RealToComplex};
+use cepstrum_extractor::num_complex::Complex;
+use cepstrum_extractor::{__kernels, CepstrumExtractor, RealToComplex};
 
 const BUF_LEN: usize = 4096;
 const WIN_LEN: usize = 128;
@@ -9,8 +10,10 @@ fn main() {
 
 fn prepare() -> (Vec<f32>, CepstrumExtractor<f32>) {
     (
-        vec![0f32; BUF_LEN],
-        CepstrumExtractor::new(WIN_LEN)
+        (0..BUF_LEN)
+            .map(|i| ((i * 7919) % 127) as f32 / 127. - 0.5)
+            .collect(),
+        CepstrumExtractor::new(WIN_LEN),
     )
 }
 
@@ -44,4 +47,76 @@ fn cceps(b: divan::Bencher) {
     b.bench_local(|| {
         divan::black_box(extractor.cceps_to_vec(&buf.to_complex_vec()));
     });
-}
\ No newline at end of file
+}
+
+const THREADS: &[usize] = &[1, 2, 4, 8];
+
+fn prepare_shared() -> (Vec<f32>, CepstrumExtractor<f32>) {
+    // Same signal as the other benches, one window long.
+    let (mut buf, extractor) = prepare();
+    buf.truncate(WIN_LEN);
+    // Thread-local scratches require the `std` feature.
+    #[cfg(feature = "std")]
+    let extractor = extractor.with_thread_local_scratches();
+
+    (buf, extractor)
+}
+
+#[divan::bench(threads = THREADS, sample_size = 1000)]
+fn rceps_into_acquire(b: divan::Bencher) {
+    let (buf, extractor) = prepare_shared();
+    // Outputs are arrays, so that no allocation is measured.
+    b.counter(divan::counter::ItemsCount::new(1usize))
+        .with_inputs(|| [0f32; WIN_LEN / 2])
+        .bench_refs(|out| extractor.acquire().rceps_into(&buf, out));
+}
+
+#[cfg(feature = "std")]
+#[divan::bench(threads = THREADS, sample_size = 1000)]
+fn rceps_into_thread_local(b: divan::Bencher) {
+    let (buf, extractor) = prepare_shared();
+    b.counter(divan::counter::ItemsCount::new(1usize))
+        .with_inputs(|| [0f32; WIN_LEN / 2])
+        .bench_refs(|out| extractor.rceps_into(&buf, out));
+}
+
+fn spectrum() -> Vec<Complex<f32>> {
+    (0..BUF_LEN)
+        .map(|i| {
+            Complex::new(
+                ((i * 7919) % 127) as f32 / 127. - 0.5,
+                ((i * 104_729) % 113) as f32 / 113. - 0.5,
+            )
+        })
+        .collect()
+}
+
+// The kernels as dispatched (vectorized, if the CPU supports it) against their scalar version.
+
+#[divan::bench(sample_size = 1000)]
+fn log_magnitude_kernel(b: divan::Bencher) {
+    let spectrum = spectrum();
+    b.with_inputs(|| spectrum.clone())
+        .bench_local_refs(|s| __kernels::log_magnitude(s));
+}
+
+#[divan::bench(sample_size = 1000)]
+fn log_magnitude_kernel_scalar(b: divan::Bencher) {
+    let spectrum = spectrum();
+    b.with_inputs(|| spectrum.clone())
+        .bench_local_refs(|s| __kernels::log_magnitude_scalar(s));
+}
+
+#[divan::bench(sample_size = 1000)]
+fn complex_log_kernel(b: divan::Bencher) {
+    let spectrum = spectrum();
+    b.with_inputs(|| spectrum.clone())
+        .bench_local_refs(|s| __kernels::complex_log(s));
+}
+
+#[divan::bench(sample_size = 1000)]
+fn complex_log_kernel_scalar(b: divan::Bencher) {
+    let spectrum = spectrum();
+    b.with_inputs(|| spectrum.clone())
+        .bench_local_refs(|s| __kernels::complex_log_scalar(s));
+}
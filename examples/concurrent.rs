@@ -24,7 +24,6 @@ fn main() {
 
     // Create an instance of the extractor long WIN_LEN.
     let extractor: Arc<CepstrumExtractor<f32>> = Arc::new(CepstrumExtractor::new(WIN_LEN));
-    extractor.extend_instances(THREADS);
 
     let chunks_per_thread = (signal.len() as f32 / WIN_LEN as f32 / THREADS as f32).ceil() as usize;
 
@@ -40,12 +39,14 @@ fn main() {
                 let out = out.clone();
 
                 s.spawn(move || {
+                    // Exclusive access to an instance of the extractor, for the whole thread.
+                    let mut instance = extractor.acquire();
+
                     chunks
                         .chunks_exact(WIN_LEN)
                         .enumerate()
                         .for_each(|(j, chunk)| {
-                            let res = extractor
-                                .rceps_with_instance_to_vec(&chunk.to_complex_vec(), i);
+                            let res = instance.rceps_to_vec(&chunk.to_complex_vec());
                             let _ = out.write().map(|mut l| {
                                 l[(i * chunks_per_thread) + j] = res;
                             });
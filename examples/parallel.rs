@@ -0,0 +1,35 @@
+use std::str::FromStr;
+use std::{env, fs};
+use cepstrum_extractor::CepstrumExtractor;
+
+const SHIFT: usize = 20;
+const WIN_LEN: usize = 128;
+const HOP: usize = WIN_LEN / 2;
+
+fn main() {
+    let mut signal: Vec<f32> = vec![];
+
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    // Load a file of 1_000_000 samples into signal.
+    for line in fs::read_to_string(format!("{crate_dir}/assets/white_noise.txt")).unwrap().lines().take(1000) {
+        signal.push(f32::from_str(line).unwrap());
+    }
+
+    // Sum signal to itself shifted by SHIFT samples
+    let signal: Vec<f32> = signal.iter()
+        .zip([0.; SHIFT].iter().chain(signal.iter()))
+        .map(|(x, y)| { *x + *y })
+        .collect();
+
+    // Create an instance of the extractor long WIN_LEN.
+    let extractor: CepstrumExtractor<f32> = CepstrumExtractor::new(WIN_LEN);
+
+    // One row of WIN_LEN / 2 values per frame.
+    let frames = extractor.frame_count(signal.len(), HOP);
+    let mut out = vec![0f32; frames * WIN_LEN / 2];
+
+    // Frames, overlapping by half, are processed across the Rayon pool.
+    extractor.par_rceps_frames(&signal, HOP, &mut out);
+
+    // `out` now contains, row by row, the cepstrums of the 128 samples long frames of the signal.
+}
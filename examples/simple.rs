@@ -21,7 +21,7 @@ fn main() {
         let mut buf = vec![0u32; W * H];
 
         let mut window = Window::new(
-            &"Example - Press <ESC> to close",
+            "Example - Press <ESC> to close",
             W,
             H,
             WindowOptions::default(),
@@ -65,7 +65,7 @@ fn main() {
         chart
             .configure_mesh()
             .label_style(("sans-serif", 15).into_font().color(&GREEN))
-            .axis_style(&GREEN)
+            .axis_style(GREEN)
             .draw()
             .unwrap();
 
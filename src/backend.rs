@@ -0,0 +1,491 @@
+//! Module containing the FFT backends the extractor can be built upon.
+//!
+//! A backend computes the forward and inverse transforms of a given length, using a scratch
+//! provided by the extractor. [`RustFftBackend`] is the default one; [`RealFftBackend`] exploits
+//! the symmetry of the spectrum of real signals and [`DftBackend`] computes the plain DFT, as a
+//! reference. [`Radix2Backend`] does not need the standard library, and is the default one without
+//! the `std` feature, together with [`DftBackend`] for lengths which are not a power of two. Any
+//! other FFT library can be plugged in by implementing [`FftBackend`] and building the extractor
+//! with [`crate::CepstrumExtractor::with_backend`].
+//!
+//! More information at: <https://en.wikipedia.org/wiki/Fast_Fourier_transform>
+
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::slice;
+
+#[cfg(feature = "std")]
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+#[cfg(feature = "std")]
+use rustfft::{Fft, FftPlanner};
+
+use crate::CepFloat;
+use crate::num_complex::Complex;
+use crate::num_traits::Zero;
+
+/// Trait implemented by the FFTs of a given length.
+///
+/// Both transforms work in place on a buffer whose length is a multiple of [`Self::len`],
+/// transforming it window by window, and are given a scratch at least [`Self::scratch_len`] long.
+/// The inverse transform is not normalised. As with `rustfft`, the backends of this module panic if
+/// the length of the buffer is not a multiple of the one of the transforms.
+///
+/// ## Examples
+/// ```rust
+/// use std::sync::Arc;
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::backend::FftBackend;
+/// use cepstrum_extractor::num_complex::Complex;
+///
+/// /// Transform of length 1, i.e. the identity.
+/// struct Identity;
+///
+/// impl FftBackend<f32> for Identity {
+///     fn len(&self) -> usize {
+///         1
+///     }
+///
+///     fn scratch_len(&self) -> usize {
+///         0
+///     }
+///
+///     fn forward(&self, _buffer: &mut [Complex<f32>], _scratch: &mut [Complex<f32>]) {}
+///
+///     fn inverse(&self, _buffer: &mut [Complex<f32>], _scratch: &mut [Complex<f32>]) {}
+/// }
+///
+/// let extractor = CepstrumExtractor::with_backend(Arc::new(Identity));
+/// ```
+#[allow(clippy::len_without_is_empty)]
+pub trait FftBackend<T: CepFloat>: Send + Sync {
+    /// Length of the transforms.
+    fn len(&self) -> usize;
+
+    /// Minimum length of the scratch passed to the transforms.
+    fn scratch_len(&self) -> usize;
+
+    /// Forward transform of `buffer`, in place.
+    fn forward(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>]);
+
+    /// Inverse (unnormalised) transform of `buffer`, in place.
+    fn inverse(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>]);
+}
+
+#[cfg(feature = "std")]
+/// Backend built upon the complex FFTs of `rustfft`; the default one. Requires the `std` feature.
+pub struct RustFftBackend<T: CepFloat> {
+    fft: Arc<dyn Fft<T>>,
+    ifft: Arc<dyn Fft<T>>,
+    scratch_len: usize,
+}
+
+#[cfg(feature = "std")]
+impl<T: CepFloat> RustFftBackend<T> {
+    /// Plans the FFTs of length `len`.
+    pub fn new(len: usize) -> Self {
+        Self::with_planner(&mut FftPlanner::new(), len)
+    }
+
+    /// As [`Self::new`], but the FFTs are planned with `planner`, re-using its plans.
+    pub fn with_planner(planner: &mut FftPlanner<T>, len: usize) -> Self {
+        let fft = planner.plan_fft_forward(len);
+        let ifft = planner.plan_fft_inverse(len);
+
+        Self {
+            scratch_len: fft
+                .get_inplace_scratch_len()
+                .max(ifft.get_inplace_scratch_len()),
+            fft,
+            ifft,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: CepFloat> FftBackend<T> for RustFftBackend<T> {
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.fft.len()
+    }
+
+    #[inline(always)]
+    fn scratch_len(&self) -> usize {
+        self.scratch_len
+    }
+
+    #[inline]
+    fn forward(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        self.fft
+            .process_with_scratch(buffer, &mut scratch[..self.scratch_len]);
+    }
+
+    #[inline]
+    fn inverse(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        self.ifft
+            .process_with_scratch(buffer, &mut scratch[..self.scratch_len]);
+    }
+}
+
+#[cfg(feature = "std")]
+/// Backend built upon the real FFTs of `realfft`, about twice as fast as [`RustFftBackend`].
+///
+/// Only the real part of the signal is transformed, i.e. the imaginary part of the input of the
+/// forward transform is discarded; the spectrum given to the inverse transform is assumed to be
+/// Hermitian, i.e. the one of a real signal, and the imaginary part of its result is always zero.
+/// This holds for the cepstrums of real signals.
+///
+/// ## Examples
+/// ```rust
+/// use std::sync::Arc;
+/// use cepstrum_extractor::{CepstrumExtractor, RealToComplex};
+/// use cepstrum_extractor::backend::RealFftBackend;
+///
+/// let extractor: CepstrumExtractor<f64> =
+///     CepstrumExtractor::with_backend(Arc::new(RealFftBackend::new(128)));
+///
+/// let ceps = extractor.rceps_to_vec(&[1.; 128].to_complex_vec());
+/// ```
+pub struct RealFftBackend<T: CepFloat> {
+    r2c: Arc<dyn RealToComplex<T>>,
+    c2r: Arc<dyn ComplexToReal<T>>,
+    scratch_len: usize,
+}
+
+#[cfg(feature = "std")]
+impl<T: CepFloat> RealFftBackend<T> {
+    /// Plans the FFTs of length `len`.
+    pub fn new(len: usize) -> Self {
+        Self::with_planner(&mut RealFftPlanner::new(), len)
+    }
+
+    /// As [`Self::new`], but the FFTs are planned with `planner`, re-using its plans.
+    pub fn with_planner(planner: &mut RealFftPlanner<T>, len: usize) -> Self {
+        let r2c = planner.plan_fft_forward(len);
+        let c2r = planner.plan_fft_inverse(len);
+
+        Self {
+            // Half spectrum, real signal and scratch of the transforms.
+            scratch_len: len / 2
+                + 1
+                + len.div_ceil(2)
+                + r2c.get_scratch_len().max(c2r.get_scratch_len()),
+            r2c,
+            c2r,
+        }
+    }
+
+    /// Splits `scratch` into the half spectrum, the real signal and the scratch of the transforms.
+    fn split<'a>(
+        &self,
+        scratch: &'a mut [Complex<T>],
+    ) -> (&'a mut [Complex<T>], &'a mut [T], &'a mut [Complex<T>]) {
+        let len = self.r2c.len();
+        let (spectrum, rest) = scratch[..self.scratch_len].split_at_mut(len / 2 + 1);
+        let (signal, rest) = rest.split_at_mut(len.div_ceil(2));
+
+        // SAFETY: `Complex<T>` is `#[repr(C)]`, i.e. laid out as two consecutive `T`.
+        let signal = unsafe { slice::from_raw_parts_mut(signal.as_mut_ptr() as *mut T, len) };
+
+        (spectrum, signal, rest)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: CepFloat> FftBackend<T> for RealFftBackend<T> {
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.r2c.len()
+    }
+
+    #[inline(always)]
+    fn scratch_len(&self) -> usize {
+        self.scratch_len
+    }
+
+    fn forward(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        let len = self.len();
+        let (spectrum, signal, scratch) = self.split(scratch);
+        check_windows(buffer, len);
+
+        for window in buffer.chunks_exact_mut(len) {
+            signal
+                .iter_mut()
+                .zip(window.iter())
+                .for_each(|(s, x)| *s = x.re);
+
+            // Lengths are right by construction.
+            let _ = self.r2c.process_with_scratch(signal, spectrum, scratch);
+
+            window[..spectrum.len()].copy_from_slice(spectrum);
+            for k in spectrum.len()..len {
+                window[k] = spectrum[len - k].conj();
+            }
+        }
+    }
+
+    fn inverse(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        let len = self.len();
+        let (spectrum, signal, scratch) = self.split(scratch);
+        check_windows(buffer, len);
+
+        for window in buffer.chunks_exact_mut(len) {
+            spectrum.copy_from_slice(&window[..spectrum.len()]);
+
+            // The imaginary parts of DC and Nyquist, which should be zero, are ignored.
+            let _ = self.c2r.process_with_scratch(spectrum, signal, scratch);
+
+            window
+                .iter_mut()
+                .zip(signal.iter())
+                .for_each(|(x, s)| *x = Complex::from(*s));
+        }
+    }
+}
+
+/// Backend computing the DFT by its definition, in `O(n^2)`; meant as a reference for the other
+/// backends.
+pub struct DftBackend<T: CepFloat> {
+    /// `e^(-2πin/len)` for `n` in `0..len`.
+    twiddles: Vec<Complex<T>>,
+}
+
+impl<T: CepFloat> DftBackend<T> {
+    /// Builds a DFT of length `len`.
+    pub fn new(len: usize) -> Self {
+        let step = -(T::PI() + T::PI()) / T::from(len).unwrap();
+
+        Self {
+            twiddles: (0..len)
+                .map(|n| Complex::from_polar(T::one(), step * T::from(n).unwrap()))
+                .collect(),
+        }
+    }
+
+    fn dft(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>], inverse: bool) {
+        let len = self.len();
+        let scratch = &mut scratch[..len];
+        check_windows(buffer, len);
+
+        for window in buffer.chunks_exact_mut(len) {
+            scratch.copy_from_slice(window);
+
+            for (k, x) in window.iter_mut().enumerate() {
+                *x = scratch
+                    .iter()
+                    .enumerate()
+                    .fold(Complex::zero(), |acc, (n, s)| {
+                        let twiddle = self.twiddles[k * n % len];
+                        acc + s * if inverse { twiddle.conj() } else { twiddle }
+                    });
+            }
+        }
+    }
+}
+
+impl<T: CepFloat> FftBackend<T> for DftBackend<T> {
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.twiddles.len()
+    }
+
+    #[inline(always)]
+    fn scratch_len(&self) -> usize {
+        self.twiddles.len()
+    }
+
+    fn forward(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        self.dft(buffer, scratch, false);
+    }
+
+    fn inverse(&self, buffer: &mut [Complex<T>], scratch: &mut [Complex<T>]) {
+        self.dft(buffer, scratch, true);
+    }
+}
+
+/// Backend computing iterative radix-2 FFTs, for lengths which are a power of two. It does not need
+/// the standard library, and is the default backend without the `std` feature.
+///
+/// ## Examples
+/// ```rust
+/// use std::sync::Arc;
+/// use cepstrum_extractor::{CepstrumExtractor, RealToComplex};
+/// use cepstrum_extractor::backend::Radix2Backend;
+///
+/// let extractor: CepstrumExtractor<f32> =
+///     CepstrumExtractor::with_backend(Arc::new(Radix2Backend::new(128)));
+///
+/// let ceps = extractor.rceps_to_vec(&[1.; 128].to_complex_vec());
+/// ```
+pub struct Radix2Backend<T: CepFloat> {
+    len: usize,
+    /// `e^(-2πin/len)` for `n` in `0..len / 2`.
+    twiddles: Vec<Complex<T>>,
+}
+
+impl<T: CepFloat> Radix2Backend<T> {
+    /// Builds an FFT of length `len`, which must be a power of two.
+    pub fn new(len: usize) -> Self {
+        assert!(len.is_power_of_two(), "Length must be a power of two");
+
+        let step = -(T::PI() + T::PI()) / T::from(len).unwrap();
+
+        Self {
+            len,
+            twiddles: (0..len / 2)
+                .map(|n| Complex::from_polar(T::one(), step * T::from(n).unwrap()))
+                .collect(),
+        }
+    }
+
+    fn fft(&self, buffer: &mut [Complex<T>], inverse: bool) {
+        check_windows(buffer, self.len);
+
+        for window in buffer.chunks_exact_mut(self.len) {
+            radix2(window, &self.twiddles, inverse);
+        }
+    }
+}
+
+/// Panics if `buffer` cannot be split into windows long `len`, rather than leaving the remainder
+/// untransformed.
+fn check_windows<T>(buffer: &[T], len: usize) {
+    assert!(
+        buffer.len().is_multiple_of(len),
+        "Buffer long {} is not a multiple of the FFT length {}",
+        buffer.len(),
+        len
+    );
+}
+
+/// In-place radix-2 FFT of `window`, whose length is a power of two, given the twiddles
+/// `e^(-2πin/window.len())` for `n` in `0..window.len() / 2` at least.
+pub(crate) fn radix2<T: CepFloat>(
+    window: &mut [Complex<T>],
+    twiddles: &[Complex<T>],
+    inverse: bool,
+) {
+    let len = window.len();
+    if len == 1 {
+        return;
+    }
+
+    let bits = len.trailing_zeros();
+    for i in 0..len {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            window.swap(i, j);
+        }
+    }
+
+    let mut half = 1;
+    while half < len {
+        let stride = len / (half * 2);
+
+        for start in (0..len).step_by(half * 2) {
+            for k in 0..half {
+                let twiddle = twiddles[k * stride];
+                let twiddle = if inverse { twiddle.conj() } else { twiddle };
+
+                let odd = window[start + k + half] * twiddle;
+                let even = window[start + k];
+                window[start + k] = even + odd;
+                window[start + k + half] = even - odd;
+            }
+        }
+
+        half *= 2;
+    }
+}
+
+impl<T: CepFloat> FftBackend<T> for Radix2Backend<T> {
+    #[inline(always)]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    fn scratch_len(&self) -> usize {
+        0
+    }
+
+    fn forward(&self, buffer: &mut [Complex<T>], _scratch: &mut [Complex<T>]) {
+        self.fft(buffer, false);
+    }
+
+    fn inverse(&self, buffer: &mut [Complex<T>], _scratch: &mut [Complex<T>]) {
+        self.fft(buffer, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{DftBackend, FftBackend, Radix2Backend};
+    #[cfg(feature = "std")]
+    use super::{RealFftBackend, RustFftBackend};
+    use crate::num_complex::Complex;
+    use crate::{CepstrumExtractor, RealToComplex};
+
+    #[test]
+    fn backends_agree() {
+        for len in [15, 16] {
+            let signal: Vec<Complex<f64>> = (0..len * 2)
+                .map(|i| (i as f64 * 0.7).sin() + (i % 3) as f64)
+                .collect::<Vec<f64>>()
+                .to_complex_vec();
+
+            let mut backends: Vec<Arc<dyn FftBackend<f64>>> = vec![Arc::new(DftBackend::new(len))];
+            #[cfg(feature = "std")]
+            backends.extend([
+                Arc::new(RustFftBackend::new(len)) as Arc<dyn FftBackend<f64>>,
+                Arc::new(RealFftBackend::new(len)),
+            ]);
+            if len.is_power_of_two() {
+                backends.push(Arc::new(Radix2Backend::new(len)));
+            }
+
+            let results: Vec<_> = backends
+                .into_iter()
+                .map(|backend| {
+                    let mut scratch = vec![Complex::default(); backend.scratch_len()];
+
+                    let mut spectrum = signal.clone();
+                    backend.forward(&mut spectrum, &mut scratch);
+                    let mut inverse = spectrum.clone();
+                    backend.inverse(&mut inverse, &mut scratch);
+
+                    let ceps = CepstrumExtractor::with_backend(backend).rceps_to_vec(&signal);
+
+                    (spectrum, inverse, ceps)
+                })
+                .collect();
+
+            let close = |a: &[Complex<f64>], b: &[Complex<f64>]| {
+                a.iter().zip(b).all(|(a, b)| (a - b).norm() < 1e-9)
+            };
+            for (spectrum, inverse, ceps) in &results[1..] {
+                assert!(close(spectrum, &results[0].0));
+                assert!(close(inverse, &results[0].1));
+                assert!(close(ceps, &results[0].2));
+            }
+
+            // The inverse is not normalised.
+            assert!(close(
+                &results[0].1,
+                &signal.iter().map(|x| x * len as f64).collect::<Vec<_>>()
+            ));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a multiple of the FFT length")]
+    fn partial_window() {
+        let backend = Radix2Backend::<f32>::new(8);
+        let mut scratch = vec![Complex::default(); backend.scratch_len()];
+
+        backend.forward(&mut [Complex::default(); 12], &mut scratch);
+    }
+}
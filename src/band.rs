@@ -0,0 +1,198 @@
+//! Band-limited real cepstrum module.
+//!
+//! Only the bins of the log spectrum within a frequency band are kept: they are shifted down to
+//! DC, mirrored so that the band spectrum stays even, and inverse transformed. Components outside
+//! the band, e.g. low-frequency structural resonances, do not affect the cepstrum, while the
+//! spacing of the harmonics and sidebands within it still shows at the right quefrency: the
+//! quefrency step is the inverse of the span of the mirrored band.
+//!
+//! More information at:
+//! - R. B. Randall, "A history of cepstrum analysis and its application to mechanical problems",
+//!   MSSP 2017.
+
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::backend::FftBackend;
+use crate::dsp::idx;
+use crate::num_complex::Complex;
+use crate::num_traits::Zero;
+use crate::{CepFloat, CepstrumExtractor, InstanceGuard};
+
+/// Band-limited cepstrum analyser: the real cepstrum of the part of the log spectrum of a frame
+/// from `f_min` to `f_max`.
+///
+/// The cepstrum is scaled as the one of [`CepstrumExtractor::rceps_to_vec`], and holds one value
+/// per quefrency in [`Self::quefrencies`].
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::band::BandCepstrum;
+/// use cepstrum_extractor::windows::hann::Hann;
+///
+/// const WIN_LEN: usize = 4096;
+///
+/// let extractor = CepstrumExtractor::new(WIN_LEN);
+/// let band = BandCepstrum::new(&extractor, 16_000., 1_000., 5_000.);
+///
+/// let ceps = band.compute(&extractor, &[0.1f32; WIN_LEN].hann_complex());
+/// assert_eq!(ceps.len(), band.quefrencies().len());
+/// ```
+pub struct BandCepstrum<T: CepFloat> {
+    win_len: usize,
+    /// Bins of the band, both included.
+    bins: (usize, usize),
+    /// Plan of the mirrored band, from the cache of the extractor.
+    plan: Arc<dyn FftBackend<T>>,
+    quefrencies: Vec<T>,
+}
+
+impl<T: CepFloat> BandCepstrum<T> {
+    /// Builds an analyser for the window length of `extractor`, keeping the bins whose frequency
+    /// lies from `f_min` to `f_max`, both included and in Hz.
+    pub fn new(extractor: &CepstrumExtractor<T>, sample_rate: T, f_min: T, f_max: T) -> Self {
+        let win_len = extractor.win_len();
+        let resolution = sample_rate / idx(win_len);
+
+        let first = (f_min.max(T::zero()) / resolution)
+            .ceil()
+            .to_usize()
+            .unwrap();
+        let last = (f_max / resolution)
+            .floor()
+            .to_usize()
+            .unwrap_or(0)
+            .min(win_len / 2);
+        assert!(first < last, "The band holds less than two bins");
+
+        let band_len = Self::band_len(first, last);
+
+        // The mirrored band spans `band_len` bins.
+        let step = T::one() / (resolution * idx(band_len));
+
+        Self {
+            win_len,
+            bins: (first, last),
+            plan: extractor.plan(band_len),
+            quefrencies: (0..band_len / 2).map(|q| step * idx(q)).collect(),
+        }
+    }
+
+    /// Length of the mirrored band from `first` to `last`.
+    #[inline(always)]
+    fn band_len(first: usize, last: usize) -> usize {
+        2 * (last - first)
+    }
+
+    /// Frequencies, in Hz, of the first and last bins of the band.
+    pub fn band(&self, sample_rate: T) -> (T, T) {
+        let resolution = sample_rate / idx(self.win_len);
+
+        (resolution * idx(self.bins.0), resolution * idx(self.bins.1))
+    }
+
+    /// Quefrencies, in seconds, of the values of the cepstrum.
+    pub fn quefrencies(&self) -> &[T] {
+        &self.quefrencies
+    }
+
+    /// Computes the band-limited cepstrum of `frame`, which should be already windowed.
+    pub fn compute(&self, extractor: &CepstrumExtractor<T>, frame: &[Complex<T>]) -> Vec<T> {
+        self.compute_with_guard(&mut extractor.default_instance(), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance held by `guard`.
+    pub fn compute_with_guard(
+        &self,
+        guard: &mut InstanceGuard<'_, T>,
+        frame: &[Complex<T>],
+    ) -> Vec<T> {
+        let (first, last) = self.bins;
+        let band_len = Self::band_len(first, last);
+
+        assert_eq!(
+            frame.len(),
+            self.win_len,
+            "Frame length differs from the analyser's one"
+        );
+        assert_eq!(
+            guard.win_len(),
+            self.win_len,
+            "Extractor length differs from the analyser's one"
+        );
+
+        let mut spectrum = frame.to_vec();
+        guard.fft_mut(&mut spectrum);
+        T::log_magnitude(&mut spectrum[first..=last]);
+
+        // Band shifted down to DC, then mirrored around its last bin.
+        let mut band = vec![Complex::zero(); band_len];
+        band[..=last - first].copy_from_slice(&spectrum[first..=last]);
+        for j in 1..last - first {
+            band[band_len - j] = band[j];
+        }
+
+        guard.ifft_with_plan_mut(self.plan.as_ref(), &mut band);
+
+        band[..band_len / 2].iter().map(|c| c.re).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BandCepstrum;
+    use crate::num_complex::Complex;
+    use crate::{CepstrumExtractor, RealToComplex};
+
+    const WIN_LEN: usize = 2048;
+    const SAMPLE_RATE: f64 = 16_000.;
+
+    #[test]
+    fn whole_band_is_rceps() {
+        let extractor: CepstrumExtractor<f64> = CepstrumExtractor::new(WIN_LEN);
+        let signal: Vec<Complex<f64>> = (0..WIN_LEN)
+            .map(|i| (i as f64 * 0.3).sin() + ((i * 7919) % 127) as f64 / 127.)
+            .collect::<Vec<f64>>()
+            .to_complex_vec();
+
+        let band = BandCepstrum::new(&extractor, SAMPLE_RATE, 0., SAMPLE_RATE / 2.);
+        let ceps = extractor.rceps_to_vec(&signal);
+
+        assert_eq!(band.quefrencies()[1], 1. / SAMPLE_RATE);
+        for (b, c) in band.compute(&extractor, &signal).iter().zip(&ceps) {
+            assert!((b - c.re).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn ignores_out_of_band_resonance() {
+        let extractor: CepstrumExtractor<f32> = CepstrumExtractor::new(WIN_LEN);
+
+        // Gear mesh harmonics of 250 Hz, between 1 and 5 kHz, plus a strong structural resonance
+        // at 90 Hz.
+        let signal: Vec<Complex<f32>> = (0..WIN_LEN)
+            .map(|i| {
+                let t = i as f64 / SAMPLE_RATE;
+                let tau = 2. * std::f64::consts::PI;
+                let mesh: f64 = (4..=20).map(|h| (tau * 250. * h as f64 * t).cos()).sum();
+                Complex::from((mesh + 50. * (tau * 90. * t).sin()) as f32)
+            })
+            .collect();
+
+        let band = BandCepstrum::new(&extractor, SAMPLE_RATE as f32, 1_000., 5_000.);
+        let ceps = band.compute_with_guard(&mut extractor.acquire(), &signal);
+
+        // Strongest rahmonic past the first few quefrencies: the period of the mesh.
+        let peak = (5..ceps.len())
+            .max_by(|a, b| ceps[*a].total_cmp(&ceps[*b]))
+            .map(|q| band.quefrencies()[q])
+            .unwrap();
+        let step = band.quefrencies()[1];
+        assert!((peak - 1. / 250.).abs() <= step);
+
+        let (low, high) = band.band(SAMPLE_RATE as f32);
+        assert!(low >= 1_000. && high <= 5_000.);
+    }
+}
@@ -1,10 +1,17 @@
 //! Module used to extract cepstrums.
 
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::backend::FftBackend;
 use crate::fft::CepFft;
-use crate::num_complex::{Complex, ComplexFloat};
+use crate::guard::{InstanceGuard, Source};
+use crate::num_complex::Complex;
+use crate::plan::PlanCache;
 use crate::CepFloat;
 
-/// The main struct of this crate; can be used to extract both complex and real cepstrums from a signal.
+/// The main struct of this crate; can be used to extract both complex and real cepstrums from a signal,
+/// as well as cross-cepstrums between two signals.
 ///
 /// As far as possible, when used multiple times, this struct will try to re-use internal data.
 ///
@@ -39,16 +46,49 @@ use crate::CepFloat;
 /// # Use in a concurrent environment
 /// This extractor can be placed in an `Arc` to be shared between different threads.
 /// You can use plain threads or external tools like Tokio and Rayon to perform parallel
-/// computations. It is important to note, however, that each thread must always use the same
-/// index to ensure that the same part of the extractor is not being used by another thread
-/// at the same time. The `*_with_instance_*` methods require a `usize` parameter, representing
-/// the index of the thread that is calling the method.
+/// computations. Each thread needs an instance of the extractor of its own: the simplest way to
+/// get one is [`Self::acquire`], which returns an [`InstanceGuard`] giving exclusive access to an
+/// instance not in use (adding a new one if they are all busy) until the guard is dropped.
+///
+/// ## Examples
+/// ```rust
+/// use std::sync::Arc;
+/// use std::thread;
+/// use cepstrum_extractor::num_complex::Complex;
+/// use cepstrum_extractor::num_traits::Zero;
+/// use cepstrum_extractor::CepstrumExtractor;
+///
+/// const THREADS: usize = 2;
+/// const CEP_LEN: usize = 10;
 ///
-/// As it is built, the extractor has only one instance available, so it can be used only by one thread
-/// at a time. By calling [`Self::extend_instances`], you can increase the number of instances.
+/// let extractor: Arc<CepstrumExtractor<f32>> = Arc::new(CepstrumExtractor::new(CEP_LEN));
 ///
-/// If you create an extractor with 10 available instances, thread indices are numbered from 0
-/// to 9.
+/// let signal: Vec<Complex<f32>> = vec![Complex::zero(); 100];
+///
+/// thread::scope(|s| {
+///     for thread_chunk in signal.chunks(signal.len() / THREADS) {
+///         let ex = extractor.clone();
+///         s.spawn(move || {
+///             let mut instance = ex.acquire();
+///             for chunk in thread_chunk.chunks(CEP_LEN) {
+///                 let complex_ceps = instance.cceps_to_vec(chunk);
+///                 let real_ceps = instance.rceps_to_vec(chunk);
+///             }
+///         });
+///     }
+/// });
+/// ```
+///
+/// If the extractor is built with [`Self::with_thread_local_scratches`], even the methods without
+/// an index can be called concurrently from any thread, each thread using a scratch of its own.
+///
+/// Alternatively, the `*_with_instance_*` methods take the index of the instance to use: as built,
+/// the extractor has only one instance, and [`Self::extend_instances`] increases their number (with
+/// 10 instances, indices go from 0 to 9). An instance can only be used by one thread at a time:
+/// using by index an instance which is already in use by another thread panics. Without
+/// thread-local scratches, the methods without an index and [`Self::acquire`] use instances of
+/// their own, kept apart from the ones used by index: any of them not in use, adding a new one if
+/// they are all busy. The two ways can then be mixed freely.
 ///
 /// ## Examples
 /// ```rust
@@ -84,39 +124,180 @@ pub struct CepstrumExtractor<T: CepFloat> {
 }
 
 impl<T: CepFloat> CepstrumExtractor<T> {
-    fn _ceps_with_instance_mut(
-        &self,
-        mut signal: &mut [Complex<T>],
-        f: fn(&Complex<T>) -> Complex<T>,
-        instance: usize,
-    ) {
-        self.fft_instance.do_fft(&mut signal, instance);
-
-        signal.iter_mut().for_each(|fft_component| {
-            *fft_component = f(fft_component);
-        });
-
-        self.fft_instance.do_ifft(&mut signal, instance);
-    }
-
     /// Builds a new extractor with a single instance available, i.e. an extractor to be used in a
     /// single-threaded environment.
+    ///
+    /// FFT plans are taken from the global cache of `T` (see [`PlanCache::global`]), so that
+    /// extractors of the same length share them.
     pub fn new(win_len: usize) -> CepstrumExtractor<T> {
         Self {
             fft_instance: CepFft::new(win_len),
         }
     }
 
+    /// As [`Self::new`], but FFT plans are taken from `cache`.
+    pub fn with_plan_cache(win_len: usize, cache: Arc<PlanCache<T>>) -> CepstrumExtractor<T> {
+        Self {
+            fft_instance: CepFft::with_cache(win_len, cache),
+        }
+    }
+
+    /// Builds a new extractor whose FFTs are computed by `backend`, with the length of the
+    /// backend; any other length (see [`Self::with_lengths`] and [`Self::set_len`]) is planned
+    /// with the global cache, i.e. computed by [`crate::backend::RustFftBackend`].
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use cepstrum_extractor::{CepstrumExtractor, RealToComplex};
+    /// use cepstrum_extractor::backend::DftBackend;
+    ///
+    /// let extractor: CepstrumExtractor<f64> =
+    ///     CepstrumExtractor::with_backend(Arc::new(DftBackend::new(64)));
+    ///
+    /// let ceps = extractor.rceps_to_vec(&[1.; 64].to_complex_vec());
+    /// ```
+    pub fn with_backend(backend: Arc<dyn FftBackend<T>>) -> CepstrumExtractor<T> {
+        Self {
+            fft_instance: CepFft::with_backend(backend, PlanCache::global()),
+        }
+    }
+
+    /// Makes the extractor serve inputs long as any of `backends` with it, as
+    /// [`Self::with_lengths`] does with the default backend.
+    pub fn with_backends(self, backends: &[Arc<dyn FftBackend<T>>]) -> Self {
+        backends
+            .iter()
+            .for_each(|backend| self.fft_instance.add_backend(backend.clone()));
+        self
+    }
+
+    /// Makes the extractor serve inputs long any of `lens` as well: an input is transformed with
+    /// the plan of its length if it is one of these, otherwise it is split into windows long as
+    /// the one of the extractor, as usual.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use cepstrum_extractor::{CepstrumExtractor, RealToComplex};
+    ///
+    /// let extractor: CepstrumExtractor<f32> = CepstrumExtractor::new(1024).with_lengths(&[256, 512]);
+    ///
+    /// let short = extractor.rceps_to_vec(&[1.; 256].to_complex_vec());
+    /// let long = extractor.rceps_to_vec(&[1.; 1024].to_complex_vec());
+    ///
+    /// assert_eq!(short.len(), 128);
+    /// assert_eq!(long.len(), 512);
+    /// ```
+    pub fn with_lengths(self, lens: &[usize]) -> Self {
+        lens.iter().for_each(|len| self.fft_instance.add_len(*len));
+        self
+    }
+
     /// Sets the length of the window to `len`.
-    pub fn set_len(&mut self, len: usize) {
+    ///
+    /// The extractor can be shared meanwhile: computations already started, as well as existing
+    /// [`InstanceGuard`]s, keep using the previous length, while the following ones use the new
+    /// one.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use cepstrum_extractor::{CepstrumExtractor, RealToComplex};
+    ///
+    /// let extractor: Arc<CepstrumExtractor<f32>> = Arc::new(CepstrumExtractor::new(256));
+    ///
+    /// let mut guard = extractor.acquire();
+    /// extractor.set_len(512);
+    ///
+    /// // The guard still uses windows long 256...
+    /// let ceps = guard.rceps_to_vec(&[1.; 256].to_complex_vec());
+    /// drop(guard);
+    ///
+    /// // ...while new computations use windows long 512.
+    /// let ceps = extractor.rceps_to_vec(&[1.; 512].to_complex_vec());
+    /// ```
+    pub fn set_len(&self, len: usize) {
         self.fft_instance.set_len(len);
     }
 
+    /// Gives every thread a scratch of its own, lazily created the first time the thread uses the
+    /// extractor. The methods without an index then use the scratch of the calling thread instead
+    /// of checking out an instance, so that calls from many threads, e.g. from a Rayon pool or a
+    /// Tokio runtime, do not contend for the instances.
+    ///
+    /// Requires the `std` feature.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use rayon::prelude::*;
+    /// use cepstrum_extractor::{CepstrumExtractor, RealToComplex};
+    ///
+    /// let extractor: Arc<CepstrumExtractor<f32>> =
+    ///     Arc::new(CepstrumExtractor::new(128).with_thread_local_scratches());
+    ///
+    /// let signal = vec![1f32; 128 * 16];
+    /// let cepstra: Vec<_> = signal
+    ///     .par_chunks_exact(128)
+    ///     .map(|chunk| extractor.rceps_to_vec(&chunk.to_complex_vec()))
+    ///     .collect();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn with_thread_local_scratches(mut self) -> Self {
+        self.fft_instance.enable_local_scratches();
+        self
+    }
+
     /// Increases the number of instances available for parallel computing to `new_count`.
     pub fn extend_instances(&self, new_count: usize) {
         self.fft_instance.extend_scratches(new_count);
     }
 
+    /// Checks out an instance not in use by anyone else, adding a new one if all of them are
+    /// busy. The instance is given back when the returned guard is dropped.
+    pub fn acquire(&self) -> InstanceGuard<'_, T> {
+        let (index, scratch) = self.fft_instance.checkout_any(true).unwrap();
+
+        InstanceGuard::new(&self.fft_instance, Source::Pooled(index), scratch)
+    }
+
+    /// As [`Self::acquire`], but returns `None` if all the instances are in use.
+    pub fn try_acquire(&self) -> Option<InstanceGuard<'_, T>> {
+        let (index, scratch) = self.fft_instance.checkout_any(false)?;
+
+        Some(InstanceGuard::new(&self.fft_instance, Source::Pooled(index), scratch))
+    }
+
+    /// Checks out the instance at index `instance`.
+    ///
+    /// Panics if the instance is already in use.
+    pub(crate) fn checkout(&self, instance: usize) -> InstanceGuard<'_, T> {
+        let scratch = self.fft_instance.checkout(instance);
+
+        InstanceGuard::new(&self.fft_instance, Source::Indexed(instance), scratch)
+    }
+
+    /// Checks out the instance used by the methods without an index: the scratch of the calling
+    /// thread if enabled, otherwise any instance not in use, as [`Self::acquire`] does.
+    pub(crate) fn default_instance(&self) -> InstanceGuard<'_, T> {
+        match self.fft_instance.checkout_local() {
+            Some(scratch) => InstanceGuard::new(&self.fft_instance, Source::Local, scratch),
+            None => self.acquire(),
+        }
+    }
+
+    /// Plan of the FFTs of length `len`, taken from the cache of the extractor; unlike
+    /// [`Self::with_lengths`], the extractor is left as it is.
+    pub(crate) fn plan(&self, len: usize) -> Arc<dyn FftBackend<T>> {
+        self.fft_instance.plan(len)
+    }
+
+    /// Length of the window the extractor has been planned for.
+    #[inline(always)]
+    pub(crate) fn win_len(&self) -> usize {
+        self.fft_instance.len()
+    }
+
     // ----------------------------------------- REAL ----------------------------------------------
 
     /// Extract the real cepstrum mutating the provided slice.
@@ -124,29 +305,16 @@ impl<T: CepFloat> CepstrumExtractor<T> {
     ///
     /// As with spectrums, the meaningful area will be `signal[0..signal.len() / 2]`.
     /// </div>
-    pub fn rceps_mut(&self, mut signal: &mut [Complex<T>]) {
-        self.rceps_with_instance_mut(&mut signal, 0);
+    pub fn rceps_mut(&self, signal: &mut [Complex<T>]) {
+        self.default_instance().rceps_mut(signal);
     }
 
     /// Extract the real cepstrum placing the result in a new vector.
     /// Such a vector will be already truncated to half `signal.len()`.
     pub fn rceps_to_vec(&self, signal: &[Complex<T>]) -> Vec<Complex<T>> {
-        let mut copied = signal.to_vec();
-
-        self.rceps_with_instance_mut(&mut copied, 0);
-        copied.truncate(copied.len() / 2);
-
-        copied
+        self.default_instance().rceps_to_vec(signal)
     }
 
-    #[inline]
-    fn r_f(x: &Complex<T>) -> Complex<T> {
-        Complex::from(if x.re == T::zero() {
-            x.abs()
-        } else {
-            x.abs().ln()
-        })
-    }
     /// As [`Self::rceps_mut`], but uses the passed instance at index `instance`.
     ///
     /// <div class="warning">
@@ -154,7 +322,7 @@ impl<T: CepFloat> CepstrumExtractor<T> {
     /// As with spectrums, the meaningful area will be `signal[0..signal.len() / 2]`.
     /// </div>
     pub fn rceps_with_instance_mut(&self, signal: &mut [Complex<T>], instance: usize) {
-        self._ceps_with_instance_mut(signal, Self::r_f, instance)
+        self.checkout(instance).rceps_mut(signal)
     }
 
     /// As [`Self::rceps_to_vec`], but uses the passed instance at index `instance`.
@@ -163,12 +331,41 @@ impl<T: CepFloat> CepstrumExtractor<T> {
         signal: &[Complex<T>],
         instance: usize,
     ) -> Vec<Complex<T>> {
-        let mut copied = signal.to_vec();
+        self.checkout(instance).rceps_to_vec(signal)
+    }
 
-        self.rceps_with_instance_mut(&mut copied, instance);
-        copied.truncate(copied.len() / 2);
+    /// Extract the real cepstrum of `signal`, which can be a slice of reals or of `Complex`,
+    /// placing its meaningful half, i.e. the real part of its first `signal.len() / 2` values, in
+    /// `out`.
+    ///
+    /// Once the scratch of the instance has been created, by a previous call or by checking it
+    /// out, no memory is allocated, so this method is suitable for real-time threads.
+    ///
+    /// Panics if `signal.len()` is neither the length of the extractor nor one of those added with
+    /// [`Self::with_lengths`], or if `out.len()` is not half of it.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use cepstrum_extractor::CepstrumExtractor;
+    ///
+    /// let extractor: CepstrumExtractor<f32> = CepstrumExtractor::new(128);
+    /// let signal = [1f32; 128];
+    /// let mut ceps = [0f32; 64];
+    ///
+    /// extractor.rceps_into(&signal, &mut ceps);
+    /// ```
+    pub fn rceps_into<S: Copy + Into<Complex<T>>>(&self, signal: &[S], out: &mut [T]) {
+        self.default_instance().rceps_into(signal, out)
+    }
 
-        copied
+    /// As [`Self::rceps_into`], but uses the passed instance at index `instance`.
+    pub fn rceps_with_instance_into<S: Copy + Into<Complex<T>>>(
+        &self,
+        signal: &[S],
+        out: &mut [T],
+        instance: usize,
+    ) {
+        self.checkout(instance).rceps_into(signal, out)
     }
 
     // --------------------------------------- COMPLEX ---------------------------------------------
@@ -178,29 +375,16 @@ impl<T: CepFloat> CepstrumExtractor<T> {
     ///
     /// As with spectrums, the meaningful area will be `signal[0..signal.len() / 2]`.
     /// </div>
-    pub fn cceps_mut(&self, mut signal: &mut [Complex<T>]) {
-        self.rceps_with_instance_mut(&mut signal, 0);
+    pub fn cceps_mut(&self, signal: &mut [Complex<T>]) {
+        self.default_instance().cceps_mut(signal);
     }
 
     /// Extract the complex cepstrum placing the result in a new vector.
     /// Such a vector will be already truncated to half `signal.len()`.
     pub fn cceps_to_vec(&self, signal: &[Complex<T>]) -> Vec<Complex<T>> {
-        let mut copied = signal.to_vec();
-
-        self.rceps_with_instance_mut(&mut copied, 0);
-        copied.truncate(copied.len() / 2);
-
-        copied
+        self.default_instance().cceps_to_vec(signal)
     }
 
-    #[inline]
-    fn c_f(x: &Complex<T>) -> Complex<T> {
-        if x.re == T::zero() {
-            *x
-        } else {
-            x.ln()
-        }
-    }
     /// As [`Self::cceps_mut`], but uses the passed instance at index `instance`.
     ///
     /// <div class="warning">
@@ -208,7 +392,7 @@ impl<T: CepFloat> CepstrumExtractor<T> {
     /// As with spectrums, the meaningful area will be `signal[0..signal.len() / 2]`.
     /// </div>
     pub fn cceps_with_instance_mut(&self, signal: &mut [Complex<T>], instance: usize) {
-        self._ceps_with_instance_mut(signal, Self::c_f, instance)
+        self.checkout(instance).cceps_mut(signal)
     }
 
     /// As [`Self::cceps_to_vec`], but uses the passed instance at index `instance`.
@@ -217,11 +401,110 @@ impl<T: CepFloat> CepstrumExtractor<T> {
         signal: &[Complex<T>],
         instance: usize,
     ) -> Vec<Complex<T>> {
-        let mut copied = signal.to_vec();
+        self.checkout(instance).cceps_to_vec(signal)
+    }
+
+    /// As [`Self::rceps_into`], but extracts the complex cepstrum, of which only the real part is
+    /// kept (the complex cepstrum of a real signal is real).
+    pub fn cceps_into<S: Copy + Into<Complex<T>>>(&self, signal: &[S], out: &mut [T]) {
+        self.default_instance().cceps_into(signal, out)
+    }
+
+    /// As [`Self::cceps_into`], but uses the passed instance at index `instance`.
+    pub fn cceps_with_instance_into<S: Copy + Into<Complex<T>>>(
+        &self,
+        signal: &[S],
+        out: &mut [T],
+        instance: usize,
+    ) {
+        self.checkout(instance).cceps_into(signal, out)
+    }
+
+    // ---------------------------------------- CROSS ----------------------------------------------
+
+    /// Extract the real cross-cepstrum of `signal` and `other`, i.e. the inverse FFT of the log
+    /// magnitude of their cross-spectrum, mutating `signal`.
+    ///
+    /// `other` is mutated as well: it will contain its own spectrum.
+    /// <div class="warning">
+    ///
+    /// As with spectrums, the meaningful area will be `signal[0..signal.len() / 2]`.
+    /// </div>
+    pub fn rxceps_mut(&self, signal: &mut [Complex<T>], other: &mut [Complex<T>]) {
+        self.default_instance().rxceps_mut(signal, other);
+    }
+
+    /// Extract the real cross-cepstrum of `signal` and `other` placing the result in a new vector.
+    /// Such a vector will be already truncated to half `signal.len()`.
+    pub fn rxceps_to_vec(&self, signal: &[Complex<T>], other: &[Complex<T>]) -> Vec<Complex<T>> {
+        self.default_instance().rxceps_to_vec(signal, other)
+    }
+
+    /// As [`Self::rxceps_mut`], but uses the passed instance at index `instance`.
+    ///
+    /// <div class="warning">
+    ///
+    /// As with spectrums, the meaningful area will be `signal[0..signal.len() / 2]`.
+    /// </div>
+    pub fn rxceps_with_instance_mut(
+        &self,
+        signal: &mut [Complex<T>],
+        other: &mut [Complex<T>],
+        instance: usize,
+    ) {
+        self.checkout(instance).rxceps_mut(signal, other)
+    }
+
+    /// As [`Self::rxceps_to_vec`], but uses the passed instance at index `instance`.
+    pub fn rxceps_with_instance_to_vec(
+        &self,
+        signal: &[Complex<T>],
+        other: &[Complex<T>],
+        instance: usize,
+    ) -> Vec<Complex<T>> {
+        self.checkout(instance).rxceps_to_vec(signal, other)
+    }
 
-        self.rceps_with_instance_mut(&mut copied, instance);
-        copied.truncate(copied.len() / 2);
+    /// Extract the complex cross-cepstrum of `signal` and `other`, i.e. the inverse FFT of the
+    /// complex logarithm (with unwrapped phase) of their cross-spectrum, mutating `signal`.
+    ///
+    /// `other` is mutated as well: it will contain its own spectrum.
+    /// <div class="warning">
+    ///
+    /// As with spectrums, the meaningful area will be `signal[0..signal.len() / 2]`.
+    /// </div>
+    pub fn cxceps_mut(&self, signal: &mut [Complex<T>], other: &mut [Complex<T>]) {
+        self.default_instance().cxceps_mut(signal, other);
+    }
+
+    /// Extract the complex cross-cepstrum of `signal` and `other` placing the result in a new
+    /// vector. Such a vector will be already truncated to half `signal.len()`.
+    pub fn cxceps_to_vec(&self, signal: &[Complex<T>], other: &[Complex<T>]) -> Vec<Complex<T>> {
+        self.default_instance().cxceps_to_vec(signal, other)
+    }
 
-        copied
+    /// As [`Self::cxceps_mut`], but uses the passed instance at index `instance`.
+    ///
+    /// <div class="warning">
+    ///
+    /// As with spectrums, the meaningful area will be `signal[0..signal.len() / 2]`.
+    /// </div>
+    pub fn cxceps_with_instance_mut(
+        &self,
+        signal: &mut [Complex<T>],
+        other: &mut [Complex<T>],
+        instance: usize,
+    ) {
+        self.checkout(instance).cxceps_mut(signal, other)
+    }
+
+    /// As [`Self::cxceps_to_vec`], but uses the passed instance at index `instance`.
+    pub fn cxceps_with_instance_to_vec(
+        &self,
+        signal: &[Complex<T>],
+        other: &[Complex<T>],
+        instance: usize,
+    ) -> Vec<Complex<T>> {
+        self.checkout(instance).cxceps_to_vec(signal, other)
     }
 }
@@ -1,5 +1,9 @@
-use rustfft::num_complex::Complex;
-use rustfft::num_traits::Num;
+use alloc::vec::Vec;
+
+use crate::CepFloat;
+use crate::dsp::{cst, idx};
+use crate::num_complex::Complex;
+use crate::num_traits::Num;
 
 /// Used to convert a slice of `Real` into a slice of `Complex`.
 pub trait RealToComplex<T> {
@@ -16,7 +20,7 @@ pub trait ComplexToReal<T> {
 /// Produces a vector of complex numbers, given a slice of real numbers.
 #[inline(always)]
 pub fn real_to_complex<T: Copy + Num>(this: &[T]) -> Vec<Complex<T>> {
-    this.iter().map(|r| Complex::from(r)).collect()
+    this.iter().map(Complex::from).collect()
 }
 
 #[inline(always)]
@@ -37,3 +41,185 @@ impl<T: Copy + Num> ComplexToReal<T> for [Complex<T>] {
         complex_to_real(self)
     }
 }
+
+/// Integer PCM sample, which can be normalised into `[-1, 1)`.
+///
+/// Implemented for `u8` (unsigned, centred on 128), `i16`, `i32` and `[u8; 3]`, i.e. a packed
+/// little-endian 24-bit sample: a buffer of packed samples can be viewed as such with
+/// `bytes.as_chunks::<3>().0`.
+pub trait PcmSample: Copy {
+    /// Value of the sample in `[-1, 1)`.
+    fn normalise<T: CepFloat>(self) -> T;
+}
+
+impl PcmSample for u8 {
+    #[inline(always)]
+    fn normalise<T: CepFloat>(self) -> T {
+        (idx::<T>(self as usize) - cst(128.)) / cst(128.)
+    }
+}
+
+impl PcmSample for i16 {
+    #[inline(always)]
+    fn normalise<T: CepFloat>(self) -> T {
+        T::from(self).unwrap() / cst(32_768.)
+    }
+}
+
+impl PcmSample for [u8; 3] {
+    #[inline(always)]
+    fn normalise<T: CepFloat>(self) -> T {
+        // Sign-extended by the arithmetic shift.
+        let value = i32::from_le_bytes([0, self[0], self[1], self[2]]) >> 8;
+        T::from(value).unwrap() / cst(8_388_608.)
+    }
+}
+
+impl PcmSample for i32 {
+    #[inline(always)]
+    fn normalise<T: CepFloat>(self) -> T {
+        T::from(self).unwrap() / cst(2_147_483_648.)
+    }
+}
+
+/// Layout of a buffer of PCM samples: the number of interleaved channels and the one to read,
+/// together with the window applied to the samples read, if any.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::{PcmFormat, PcmToReal};
+/// use cepstrum_extractor::windows::hann::Hann;
+///
+/// // Stereo frames of 4 samples per channel.
+/// let stereo: [i16; 8] = [0, 100, 16_384, 200, -16_384, 300, -32_768, 400];
+///
+/// // Computed once, then used for every frame.
+/// let mut window = [1f32; 4];
+/// window.hann();
+/// let format = PcmFormat::new().with_channel(2, 0).with_window(&window);
+///
+/// // Reused frame after frame: no allocation once it is large enough.
+/// let mut left = Vec::with_capacity(4);
+/// stereo.pcm_to_real_into(&format, &mut left);
+///
+/// assert_eq!(left.len(), 4);
+/// assert!((left[1] - 0.375).abs() < 1e-6);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct PcmFormat<'a, T> {
+    channels: usize,
+    channel: usize,
+    window: Option<&'a [T]>,
+}
+
+impl<'a, T> PcmFormat<'a, T> {
+    /// Single channel, without window.
+    pub fn new() -> Self {
+        Self {
+            channels: 1,
+            channel: 0,
+            window: None,
+        }
+    }
+
+    /// Reads only `channel` out of `channels` interleaved ones.
+    pub fn with_channel(mut self, channels: usize, channel: usize) -> Self {
+        assert!(channel < channels, "Channel out of bounds: {}", channel);
+
+        self.channels = channels;
+        self.channel = channel;
+        self
+    }
+
+    /// Multiplies every sample read by the corresponding coefficient of `window`, e.g. one
+    /// computed once by applying a window of [`crate::windows`] to a slice of ones.
+    pub fn with_window(mut self, window: &'a [T]) -> Self {
+        self.window = Some(window);
+        self
+    }
+}
+
+impl<T> Default for PcmFormat<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Used to normalise a slice of integer PCM samples into reals, de-interleaving a channel and
+/// applying a window in the same pass (see [`PcmFormat`]).
+pub trait PcmToReal<T> {
+    /// Replaces the content of `out` with the normalised samples; `out` only allocates if its
+    /// capacity is not enough.
+    fn pcm_to_real_into(&self, format: &PcmFormat<T>, out: &mut Vec<T>);
+
+    /// As [`Self::pcm_to_real_into`], but produces complex numbers, ready for the `*_mut` methods
+    /// of the extractor.
+    fn pcm_to_complex_into(&self, format: &PcmFormat<T>, out: &mut Vec<Complex<T>>);
+}
+
+/// Normalised samples read from `pcm` according to `format`.
+#[inline(always)]
+fn pcm_samples<'a, S: PcmSample, T: CepFloat>(
+    pcm: &'a [S],
+    format: &'a PcmFormat<T>,
+) -> impl Iterator<Item = T> + 'a {
+    if let Some(window) = format.window {
+        assert_eq!(
+            window.len(),
+            (pcm.len() + format.channels - 1 - format.channel) / format.channels,
+            "Window length differs from the number of samples read"
+        );
+    }
+
+    let samples = pcm
+        .iter()
+        .skip(format.channel)
+        .step_by(format.channels)
+        .map(|s| s.normalise::<T>());
+
+    samples
+        .enumerate()
+        .map(|(i, s)| format.window.map_or(s, |window| s * window[i]))
+}
+
+impl<S: PcmSample, T: CepFloat> PcmToReal<T> for [S] {
+    fn pcm_to_real_into(&self, format: &PcmFormat<T>, out: &mut Vec<T>) {
+        out.clear();
+        out.extend(pcm_samples(self, format));
+    }
+
+    fn pcm_to_complex_into(&self, format: &PcmFormat<T>, out: &mut Vec<Complex<T>>) {
+        out.clear();
+        out.extend(pcm_samples(self, format).map(Complex::from));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PcmFormat, PcmSample, PcmToReal};
+    use crate::num_complex::Complex;
+
+    #[test]
+    fn normalised_pcm() {
+        assert_eq!(i16::MIN.normalise::<f32>(), -1.);
+        assert_eq!(i16::MAX.normalise::<f64>(), 32_767. / 32_768.);
+        assert_eq!(i32::MIN.normalise::<f64>(), -1.);
+        assert_eq!(0u8.normalise::<f32>(), -1.);
+        assert_eq!(192u8.normalise::<f32>(), 0.5);
+        assert_eq!([0x00, 0x00, 0x80].normalise::<f64>(), -1.);
+        assert_eq!([0x00, 0x00, 0x40].normalise::<f64>(), 0.5);
+        assert_eq!([0xff, 0xff, 0xff].normalise::<f64>(), -1. / 8_388_608.);
+
+        // Packed 24-bit samples of 3 channels, of which the last one is read.
+        let bytes = [0u8, 0, 0, 1, 0, 0, 0, 0, 0x40, 0, 0, 0, 0, 0, 0, 0, 0, 0xc0];
+        let window = [2., 0.5];
+        let format = PcmFormat::new().with_channel(3, 2).with_window(&window);
+
+        let mut out: Vec<Complex<f64>> = Vec::with_capacity(2);
+        let ptr = out.as_ptr();
+        bytes.as_chunks::<3>().0.pcm_to_complex_into(&format, &mut out);
+
+        assert_eq!(out, [Complex::from(1.), Complex::from(-0.25)]);
+        assert_eq!(out.as_ptr(), ptr);
+    }
+}
@@ -0,0 +1,224 @@
+//! Distance measures between cepstra.
+//!
+//! All the functions accept slices of any [`Coefficient`], so they can be used both on the output
+//! of [`crate::CepstrumExtractor`] and on the features computed by the modules in
+//! [`crate::features`].
+//!
+//! <div class="warning">
+//!
+//! The inverse FFT used by [`crate::CepstrumExtractor`] is not normalised, so its cepstra are scaled
+//! by the length of the window. Divide them by such a length before computing distances expressed
+//! in log-spectral units (e.g. [`mel_cepstral_distortion`]).
+//! </div>
+//!
+//! More information at:
+//! - L. Rabiner, B.-H. Juang, "Fundamentals of Speech Recognition", sec. 4.5.
+//! - R. Kubichek, "Mel-cepstral distance measure for objective speech quality assessment", 1993.
+
+use alloc::vec::Vec;
+
+use crate::dsp::{cst, idx};
+use crate::dtw::{Dtw, StepPattern};
+use crate::{CepFloat, Coefficient};
+
+/// Truncated Euclidean cepstral distance between `a` and `b`, using the coefficients `0..=order`.
+///
+/// The distance is computed as `sqrt((a[0] - b[0])^2 + 2 * sum((a[k] - b[k])^2))`, with `k` in
+/// `1..=order`; given the real cepstra of the log amplitude spectra, this approximates the RMS
+/// log-spectral distance (in nepers), which it equals as `order` grows.
+pub fn cepstral_distance<T: CepFloat, C: Coefficient<T>>(a: &[C], b: &[C], order: usize) -> T {
+    assert!(
+        a.len() > order && b.len() > order,
+        "Cepstra shorter than order {}",
+        order
+    );
+
+    let d0 = a[0].value() - b[0].value();
+    let rest = squared_difference(&a[1..=order], &b[1..=order], |_| T::one());
+
+    (d0 * d0 + cst::<T>(2.) * rest).sqrt()
+}
+
+/// Euclidean distance between `a` and `b`, using all the coefficients.
+pub fn euclidean_distance<T: CepFloat, C: Coefficient<T>>(a: &[C], b: &[C]) -> T {
+    assert_eq!(a.len(), b.len(), "Cepstra of different length");
+
+    squared_difference(a, b, |_| T::one()).sqrt()
+}
+
+/// Weighted cepstral distance: `sqrt(sum(weights[k] * (a[k] - b[k])^2))`, with `k` in
+/// `0..weights.len()`.
+///
+/// Setting `weights[k] = lifter[k]^2` yields the distance between the liftered cepstra; see
+/// [`sinusoidal_lifter`] and [`index_lifter`].
+pub fn weighted_cepstral_distance<T: CepFloat, C: Coefficient<T>>(
+    a: &[C],
+    b: &[C],
+    weights: &[T],
+) -> T {
+    let len = weights.len();
+    assert!(
+        a.len() >= len && b.len() >= len,
+        "Cepstra shorter than weights"
+    );
+
+    squared_difference(&a[..len], &b[..len], |k| weights[k]).sqrt()
+}
+
+/// Liftered cepstral distance: `sqrt(sum((lifter[k] * (a[k] - b[k]))^2))`, with `k` in
+/// `0..lifter.len()`.
+pub fn liftered_cepstral_distance<T: CepFloat, C: Coefficient<T>>(
+    a: &[C],
+    b: &[C],
+    lifter: &[T],
+) -> T {
+    let len = lifter.len();
+    assert!(
+        a.len() >= len && b.len() >= len,
+        "Cepstra shorter than lifter"
+    );
+
+    squared_difference(&a[..len], &b[..len], |k| lifter[k] * lifter[k]).sqrt()
+}
+
+/// Band-pass (sinusoidal) lifter of length `len`: `1 + l / 2 * sin(pi * k / l)`.
+///
+/// The coefficient `0` is set to zero, so that the energy term does not contribute.
+pub fn sinusoidal_lifter<T: CepFloat>(len: usize, l: usize) -> Vec<T> {
+    let l = idx::<T>(l);
+
+    (0..len)
+        .map(|k| {
+            if k == 0 {
+                T::zero()
+            } else {
+                T::one() + l / cst(2.) * (T::PI() * idx(k) / l).sin()
+            }
+        })
+        .collect()
+}
+
+/// Index lifter of length `len`: `k`, i.e. the weighting of the root power sums measure.
+pub fn index_lifter<T: CepFloat>(len: usize) -> Vec<T> {
+    (0..len).map(idx).collect()
+}
+
+/// Mel-cepstral distortion, in dB, between the frames `a` and `b`, using the coefficients
+/// `1..=order` (the energy term `0` is excluded).
+///
+/// `MCD = 10 / ln(10) * sqrt(2 * sum((a[k] - b[k])^2))`.
+pub fn mel_cepstral_distortion<T: CepFloat, C: Coefficient<T>>(
+    a: &[C],
+    b: &[C],
+    order: usize,
+) -> T {
+    assert!(
+        a.len() > order && b.len() > order,
+        "Cepstra shorter than order {}",
+        order
+    );
+
+    let sum = squared_difference(&a[1..=order], &b[1..=order], |_| T::one());
+
+    cst::<T>(10.) / T::LN_10() * (cst::<T>(2.) * sum).sqrt()
+}
+
+/// Mean mel-cepstral distortion, in dB, between two sequences of frames.
+///
+/// If `align` is `false`, frames are compared pairwise and the sequences must have the same length;
+/// otherwise the sequences are first aligned with dynamic time warping (see [`crate::dtw`]), and
+/// the distortion is averaged along the warping path.
+pub fn mel_cepstral_distortion_sequence<T, C, S>(a: &[S], b: &[S], order: usize, align: bool) -> T
+where
+    T: CepFloat,
+    C: Coefficient<T>,
+    S: AsRef<[C]>,
+{
+    assert!(!a.is_empty() && !b.is_empty(), "Empty sequence");
+
+    let mcd = |i: usize, j: usize| mel_cepstral_distortion(a[i].as_ref(), b[j].as_ref(), order);
+
+    if align {
+        let path = Dtw::new()
+            .with_step_pattern(StepPattern::Symmetric1)
+            .align_with(a.len(), b.len(), mcd)
+            .unwrap()
+            .path;
+        path.iter().fold(T::zero(), |acc, (i, j)| acc + mcd(*i, *j)) / idx(path.len())
+    } else {
+        assert_eq!(a.len(), b.len(), "Sequences of different length");
+        (0..a.len()).fold(T::zero(), |acc, i| acc + mcd(i, i)) / idx(a.len())
+    }
+}
+
+#[inline]
+fn squared_difference<T: CepFloat, C: Coefficient<T>>(
+    a: &[C],
+    b: &[C],
+    weight: impl Fn(usize) -> T,
+) -> T {
+    a.iter()
+        .zip(b.iter())
+        .enumerate()
+        .fold(T::zero(), |acc, (k, (a, b))| {
+            let d = a.value() - b.value();
+            acc + weight(k) * d * d
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CepstrumExtractor;
+    use crate::num_complex::Complex;
+    use crate::num_traits::Zero;
+
+    const WIN_LEN: usize = 512;
+
+    /// Real cepstrum of the impulse response `1 + a z^-1`, normalised.
+    fn ceps(extractor: &CepstrumExtractor<f64>, a: f64) -> Vec<Complex<f64>> {
+        let mut signal = vec![Complex::zero(); WIN_LEN];
+        signal[0] = Complex::from(1.);
+        signal[1] = Complex::from(a);
+
+        extractor
+            .rceps_to_vec(&signal)
+            .iter()
+            .map(|c| c / WIN_LEN as f64)
+            .collect()
+    }
+
+    #[test]
+    fn approximates_log_spectral_distance() {
+        let extractor = CepstrumExtractor::new(WIN_LEN);
+        let (a, b) = (0.5, -0.3);
+
+        let rms = ((0..WIN_LEN)
+            .map(|k| {
+                let w = std::f64::consts::TAU * k as f64 / WIN_LEN as f64;
+                let la = (Complex::new(1., 0.) + Complex::from_polar(a, -w))
+                    .norm()
+                    .ln();
+                let lb = (Complex::new(1., 0.) + Complex::from_polar(b, -w))
+                    .norm()
+                    .ln();
+                (la - lb).powi(2)
+            })
+            .sum::<f64>()
+            / WIN_LEN as f64)
+            .sqrt();
+
+        let d = cepstral_distance(&ceps(&extractor, a), &ceps(&extractor, b), 40);
+
+        assert!((d - rms).abs() < 1e-6, "{} vs {}", d, rms);
+    }
+
+    #[test]
+    fn aligned_mcd() {
+        let a: Vec<Vec<f64>> = vec![vec![0., 1., 2.], vec![0., 3., 4.], vec![0., 5., 6.]];
+        let b: Vec<Vec<f64>> = vec![a[0].clone(), a[0].clone(), a[1].clone(), a[2].clone()];
+
+        assert_eq!(mel_cepstral_distortion_sequence(&a, &b, 2, true), 0.);
+        assert!(mel_cepstral_distortion_sequence(&a, &b[..3], 2, false) > 0.);
+    }
+}
@@ -0,0 +1,170 @@
+//! Small signal processing helpers shared by the analysis modules.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::CepFloat;
+use crate::num_complex::Complex;
+
+/// Converts an `f64` constant into `T`.
+#[inline(always)]
+pub(crate) fn cst<T: CepFloat>(x: f64) -> T {
+    T::from_f64(x).unwrap()
+}
+
+/// Converts a `usize` into `T`.
+#[inline(always)]
+pub(crate) fn idx<T: CepFloat>(x: usize) -> T {
+    T::from_usize(x).unwrap()
+}
+
+/// Levinson-Durbin recursion.
+///
+/// Given the autocorrelation `r[0..=order]`, returns the coefficients of the inverse filter
+/// `A(z) = 1 + a[1] z^-1 + ... + a[order] z^-order` (with `a[0] = 1`) and the power of the
+/// prediction error.
+pub(crate) fn levinson<T: CepFloat>(r: &[T], order: usize) -> (Vec<T>, T) {
+    assert!(
+        r.len() > order,
+        "Autocorrelation too short for order {}",
+        order
+    );
+
+    let mut a = vec![T::zero(); order + 1];
+    a[0] = T::one();
+
+    let mut err = r[0];
+    if err <= T::zero() {
+        return (a, T::zero());
+    }
+
+    let mut prev = a.clone();
+    for i in 1..=order {
+        let acc = (1..i).fold(r[i], |acc, j| acc + a[j] * r[i - j]);
+        let k = -acc / err;
+
+        prev.copy_from_slice(&a);
+        for j in 1..i {
+            a[j] = prev[j] + k * prev[i - j];
+        }
+        a[i] = k;
+
+        err = err * (T::one() - k * k);
+        if err <= T::zero() {
+            err = T::zero();
+            break;
+        }
+    }
+
+    (a, err)
+}
+
+/// Converts the all-pole model `err / |A(e^jw)|^2` into `n` cepstral coefficients.
+///
+/// `c[0]` is the log of the prediction error power; the others follow the usual recursion.
+pub(crate) fn lpc_to_cepstrum<T: CepFloat>(a: &[T], err: T, n: usize) -> Vec<T> {
+    let order = a.len() - 1;
+    let mut c = vec![T::zero(); n];
+
+    if n == 0 {
+        return c;
+    }
+    c[0] = err.max(T::min_positive_value()).ln();
+
+    for m in 1..n {
+        let mut acc = if m <= order { -a[m] } else { T::zero() };
+        for k in 1..m {
+            if m - k <= order {
+                acc = acc - idx::<T>(k) / idx::<T>(m) * c[k] * a[m - k];
+            }
+        }
+        c[m] = acc;
+    }
+
+    c
+}
+
+/// Orthonormal DCT-II of `x`, keeping only the first `n` coefficients.
+pub(crate) fn dct2<T: CepFloat>(x: &[T], n: usize) -> Vec<T> {
+    let len = idx::<T>(x.len());
+    let scale0 = (T::one() / len).sqrt();
+    let scale = (cst::<T>(2.) / len).sqrt();
+
+    (0..n)
+        .map(|q| {
+            let sum = x.iter().enumerate().fold(T::zero(), |acc, (i, v)| {
+                acc + *v * (T::PI() * idx(q) * (idx::<T>(i) + cst(0.5)) / len).cos()
+            });
+
+            sum * if q == 0 { scale0 } else { scale }
+        })
+        .collect()
+}
+
+/// Triangular filterbank over the `0..=win_len / 2` bins of a spectrum sampled at `sample_rate`.
+///
+/// Filter `i` rises from `edges[i]` to `edges[i + 1]` and falls to `edges[i + 2]` (all in Hz), so
+/// `edges.len() - 2` filters are built.
+pub(crate) fn triangular_filterbank<T: CepFloat>(
+    edges: &[T],
+    win_len: usize,
+    sample_rate: T,
+) -> Vec<Vec<T>> {
+    edges
+        .windows(3)
+        .map(|e| {
+            (0..=win_len / 2)
+                .map(|k| {
+                    let f = idx::<T>(k) * sample_rate / idx(win_len);
+
+                    if f > e[0] && f <= e[1] {
+                        (f - e[0]) / (e[1] - e[0])
+                    } else if f > e[1] && f < e[2] {
+                        (e[2] - f) / (e[2] - e[1])
+                    } else {
+                        T::zero()
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Roots of the polynomial `z^n + a[1] z^(n-1) + ... + a[n]` (`a[0]` is ignored and taken as `1`),
+/// found with the Durand-Kerner iteration.
+pub(crate) fn polynomial_roots<T: CepFloat>(a: &[T]) -> Vec<Complex<T>> {
+    let n = a.len().saturating_sub(1);
+    let eval = |z: Complex<T>| {
+        a[1..]
+            .iter()
+            .fold(Complex::from(T::one()), |acc, c| acc * z + *c)
+    };
+
+    let seed = Complex::new(cst::<T>(0.4), cst::<T>(0.9));
+    let mut roots: Vec<Complex<T>> = (0..n).map(|i| seed.powu(i as u32)).collect();
+
+    for _ in 0..500 {
+        let mut change = T::zero();
+
+        for i in 0..n {
+            let den = (0..n)
+                .filter(|j| *j != i)
+                .fold(Complex::from(T::one()), |acc, j| {
+                    acc * (roots[i] - roots[j])
+                });
+            if den.norm_sqr() == T::zero() {
+                continue;
+            }
+
+            let step = eval(roots[i]) / den;
+            roots[i] = roots[i] - step;
+            change = change.max(step.norm());
+        }
+
+        if change < cst(1e-12) {
+            break;
+        }
+    }
+
+    roots
+}
@@ -0,0 +1,288 @@
+//! Dynamic time warping (DTW) module, used to align sequences of cepstral vectors.
+//!
+//! More information at:
+//! - H. Sakoe, S. Chiba, "Dynamic programming algorithm optimization for spoken word
+//!   recognition", IEEE TASSP 1978.
+//! - F. Itakura, "Minimum prediction residual principle applied to speech recognition",
+//!   IEEE TASSP 1975.
+//! - T. Giorgino, "Computing and visualizing dynamic time warping alignments in R: the dtw
+//!   package", JSS 2009.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::CepFloat;
+use crate::dsp::{cst, idx};
+
+/// Global constraint limiting the region of the cost matrix the path can go through.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window<T: CepFloat> {
+    /// No constraint.
+    None,
+    /// Sakoe-Chiba band: cells farther than `radius` frames from the (scaled) diagonal are
+    /// excluded.
+    SakoeChiba(usize),
+    /// Itakura parallelogram: the path slope must stay within `1 / max_slope..=max_slope`
+    /// (usually `2`).
+    Itakura(T),
+}
+
+/// Local constraint: the steps a path can take and their weights.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepPattern {
+    /// Steps `(1, 0)`, `(0, 1)` and `(1, 1)`, all weighted `1`. Costs are normalised by the length
+    /// of the path.
+    Symmetric1,
+    /// Steps `(1, 0)`, `(0, 1)`, weighted `1`, and `(1, 1)`, weighted `2`. Costs are normalised by
+    /// `n + m`.
+    Symmetric2,
+    /// Steps `(1, 0)`, `(1, 1)` and `(1, 2)`, all weighted `1`: every frame of the first sequence
+    /// is used exactly once. Costs are normalised by `n`.
+    Asymmetric,
+}
+
+impl StepPattern {
+    /// Steps as `(di, dj, weight)`.
+    fn steps(&self) -> &'static [(usize, usize, f64)] {
+        match self {
+            StepPattern::Symmetric1 => &[(1, 1, 1.), (1, 0, 1.), (0, 1, 1.)],
+            StepPattern::Symmetric2 => &[(1, 1, 2.), (1, 0, 1.), (0, 1, 1.)],
+            StepPattern::Asymmetric => &[(1, 1, 1.), (1, 0, 1.), (1, 2, 1.)],
+        }
+    }
+}
+
+/// Result of an alignment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alignment<T: CepFloat> {
+    /// Pairs `(i, j)` of aligned frames, from `(0, 0)` to `(n - 1, m - 1)`.
+    pub path: Vec<(usize, usize)>,
+    /// Accumulated cost of the path.
+    pub cost: T,
+    /// Accumulated cost, normalised as prescribed by the [`StepPattern`].
+    pub normalized_cost: T,
+}
+
+/// DTW aligner.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::distance::euclidean_distance;
+/// use cepstrum_extractor::dtw::{Dtw, StepPattern, Window};
+///
+/// let template: Vec<Vec<f32>> = vec![vec![0., 1.], vec![1., 1.], vec![2., 0.]];
+/// let query: Vec<Vec<f32>> = vec![vec![0., 1.], vec![0., 1.], vec![1., 1.], vec![2., 0.]];
+///
+/// let dtw = Dtw::new()
+///     .with_window(Window::SakoeChiba(2))
+///     .with_step_pattern(StepPattern::Symmetric2);
+///
+/// let alignment = dtw.align(&template, &query, euclidean_distance).unwrap();
+/// assert_eq!(alignment.cost, 0.);
+/// assert_eq!(alignment.path, vec![(0, 0), (0, 1), (1, 2), (2, 3)]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dtw<T: CepFloat> {
+    window: Window<T>,
+    step_pattern: StepPattern,
+}
+
+impl<T: CepFloat> Default for Dtw<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: CepFloat> Dtw<T> {
+    /// Builds a new aligner with no global constraint and the [`StepPattern::Symmetric2`] pattern.
+    pub fn new() -> Self {
+        Self {
+            window: Window::None,
+            step_pattern: StepPattern::Symmetric2,
+        }
+    }
+
+    /// Sets the global constraint.
+    pub fn with_window(mut self, window: Window<T>) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the local constraint.
+    pub fn with_step_pattern(mut self, step_pattern: StepPattern) -> Self {
+        self.step_pattern = step_pattern;
+        self
+    }
+
+    /// Aligns the sequences of frames `a` and `b`, measuring the distance between two frames with
+    /// `distance`; any function of [`crate::distance`] taking two frames can be used, as well as
+    /// custom closures.
+    ///
+    /// Returns `None` if the constraints leave no path between the ends of the sequences.
+    pub fn align<C, S: AsRef<[C]>>(
+        &self,
+        a: &[S],
+        b: &[S],
+        distance: impl Fn(&[C], &[C]) -> T,
+    ) -> Option<Alignment<T>> {
+        self.align_with(a.len(), b.len(), |i, j| {
+            distance(a[i].as_ref(), b[j].as_ref())
+        })
+    }
+
+    /// Aligns two sequences long `n` and `m`, given a function returning the local cost of the cell
+    /// `(i, j)`.
+    ///
+    /// Returns `None` if the constraints leave no path between `(0, 0)` and `(n - 1, m - 1)`.
+    pub fn align_with(
+        &self,
+        n: usize,
+        m: usize,
+        cost: impl Fn(usize, usize) -> T,
+    ) -> Option<Alignment<T>> {
+        if n == 0 || m == 0 {
+            return None;
+        }
+
+        let steps = self.step_pattern.steps();
+        let mut acc = vec![T::infinity(); n * m];
+        let mut from = vec![usize::MAX; n * m];
+
+        for i in 0..n {
+            for j in 0..m {
+                if !self.allowed(i, j, n, m) {
+                    continue;
+                }
+
+                let d = cost(i, j);
+                if i == 0 && j == 0 {
+                    acc[0] = d;
+                    continue;
+                }
+
+                for (s, (di, dj, w)) in steps.iter().enumerate() {
+                    if *di > i || *dj > j {
+                        continue;
+                    }
+
+                    let c = acc[(i - di) * m + j - dj] + cst::<T>(*w) * d;
+                    if c < acc[i * m + j] {
+                        acc[i * m + j] = c;
+                        from[i * m + j] = s;
+                    }
+                }
+            }
+        }
+
+        let cost = acc[n * m - 1];
+        if !cost.is_finite() {
+            return None;
+        }
+
+        let (mut i, mut j) = (n - 1, m - 1);
+        let mut path = vec![(i, j)];
+        while i > 0 || j > 0 {
+            let (di, dj, _) = steps[from[i * m + j]];
+            (i, j) = (i - di, j - dj);
+            path.push((i, j));
+        }
+        path.reverse();
+
+        let norm = match self.step_pattern {
+            StepPattern::Symmetric1 => idx(path.len()),
+            StepPattern::Symmetric2 => idx(n + m),
+            StepPattern::Asymmetric => idx(n),
+        };
+
+        Some(Alignment {
+            path,
+            cost,
+            normalized_cost: cost / norm,
+        })
+    }
+
+    fn allowed(&self, i: usize, j: usize, n: usize, m: usize) -> bool {
+        match self.window {
+            Window::None => true,
+            Window::SakoeChiba(radius) => {
+                let diag = if n > 1 {
+                    idx::<T>(i) * idx(m - 1) / idx(n - 1)
+                } else {
+                    T::zero()
+                };
+                (idx::<T>(j) - diag).abs() <= idx(radius)
+            }
+            Window::Itakura(slope) => {
+                let (i, j) = (idx::<T>(i), idx::<T>(j));
+                let (n, m) = (idx::<T>(n - 1), idx::<T>(m - 1));
+
+                j <= slope * i
+                    && j >= i / slope
+                    && j >= m - slope * (n - i)
+                    && j <= m - (n - i) / slope
+                    || (i == T::zero() && j == T::zero())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dtw, StepPattern, Window};
+
+    fn abs(a: &[f64], b: &[f64]) -> f64 {
+        (a[0] - b[0]).abs()
+    }
+
+    fn seq(v: &[f64]) -> Vec<[f64; 1]> {
+        v.iter().map(|x| [*x]).collect()
+    }
+
+    #[test]
+    fn stretched_sequence() {
+        let a = seq(&[0., 1., 2., 3., 2., 1.]);
+        let b = seq(&[0., 0., 1., 1., 2., 2., 3., 3., 2., 2., 1.]);
+
+        for pattern in [
+            StepPattern::Symmetric1,
+            StepPattern::Symmetric2,
+            StepPattern::Asymmetric,
+        ] {
+            let al = Dtw::new()
+                .with_step_pattern(pattern)
+                .align(&a, &b, abs)
+                .unwrap();
+
+            assert_eq!(al.cost, 0.);
+            assert_eq!(*al.path.first().unwrap(), (0, 0));
+            assert_eq!(*al.path.last().unwrap(), (5, 10));
+        }
+    }
+
+    #[test]
+    fn constraints() {
+        let a = seq(&[0., 1., 2.]);
+        let b = seq(&[0., 0., 0., 0., 0., 0., 0., 1., 2.]);
+
+        assert!(Dtw::new().align(&a, &b, abs).is_some());
+        assert!(
+            Dtw::new()
+                .with_window(Window::SakoeChiba(3))
+                .align(&a, &b, abs)
+                .is_some()
+        );
+        assert!(
+            Dtw::new()
+                .with_window(Window::Itakura(2.))
+                .align(&a, &b, abs)
+                .is_none()
+        );
+
+        let free = Dtw::new().align(&a, &b, abs).unwrap();
+        let band = Dtw::new()
+            .with_window(Window::SakoeChiba(3))
+            .align(&a, &b, abs)
+            .unwrap();
+        assert!(band.cost > free.cost);
+    }
+}
@@ -0,0 +1,278 @@
+//! Constant-Q cepstral coefficients (CQCC) module.
+//!
+//! The constant-Q transform is computed frame by frame through spectral kernels, as described in
+//! J. C. Brown, M. S. Puckette, "An efficient algorithm for the calculation of a constant Q
+//! transform", JASA 1992.
+//!
+//! More information at:
+//! - M. Todisco, H. Delgado, N. Evans, "Constant Q cepstral coefficients: A spoofing
+//!   countermeasure for automatic speaker verification", Computer Speech & Language 2017.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::dsp::{cst, dct2, idx};
+use crate::num_complex::Complex;
+use crate::num_traits::Zero;
+use crate::{CepFloat, CepstrumExtractor, InstanceGuard};
+
+/// Spectral kernel of a single constant-Q bin; only the significant span is stored.
+struct Kernel<T: CepFloat> {
+    start: usize,
+    coeffs: Vec<Complex<T>>,
+}
+
+/// CQCC analyser: log power of a constant-Q transform, resampled onto a uniform frequency axis and
+/// decorrelated with a DCT.
+///
+/// The longest constant-Q kernel, i.e. the one of the lowest bin, must fit into the window:
+/// `win_len` must be at least `Q * sample_rate / f_min`, with `Q = 1 / (2^(1 / bins_per_octave) - 1)`.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::features::cqcc::Cqcc;
+/// use cepstrum_extractor::windows::hann::Hann;
+///
+/// const WIN_LEN: usize = 4096;
+///
+/// let extractor = CepstrumExtractor::new(WIN_LEN);
+/// let cqcc = Cqcc::new(&extractor, 16_000., 100., 12, 20);
+///
+/// let ceps = cqcc.compute(&extractor, &[0.1f32; WIN_LEN].hann_complex());
+/// assert_eq!(ceps.len(), 20);
+/// ```
+pub struct Cqcc<T: CepFloat> {
+    win_len: usize,
+    n_ceps: usize,
+    frequencies: Vec<T>,
+    kernels: Vec<Kernel<T>>,
+    /// For every point of the uniform axis, the lower constant-Q bin and the interpolation weight
+    /// of the upper one.
+    resampling: Vec<(usize, T)>,
+}
+
+impl<T: CepFloat> Cqcc<T> {
+    /// Number of uniform samples the first octave is resampled to.
+    const FIRST_OCTAVE_SAMPLES: usize = 16;
+    /// Kernel coefficients below this fraction of the kernel's peak are discarded.
+    const KERNEL_THRESHOLD: f64 = 1e-3;
+
+    /// Builds a new CQCC analyser for the frames handled by `extractor`, sampled at `sample_rate`.
+    ///
+    /// The constant-Q transform has `bins_per_octave` bins per octave, starting from `f_min` up to
+    /// the Nyquist frequency; `n_ceps` coefficients are returned.
+    pub fn new(
+        extractor: &CepstrumExtractor<T>,
+        sample_rate: T,
+        f_min: T,
+        bins_per_octave: usize,
+        n_ceps: usize,
+    ) -> Self {
+        let win_len = extractor.win_len();
+        let nyquist = sample_rate / cst(2.);
+        assert!(
+            f_min > T::zero() && f_min < nyquist,
+            "Invalid f_min: {:?}",
+            f_min
+        );
+
+        let q = T::one() / (cst::<T>(2.).powf(T::one() / idx(bins_per_octave)) - T::one());
+
+        let frequencies: Vec<T> = (0..)
+            .map(|k| f_min * cst::<T>(2.).powf(idx::<T>(k) / idx(bins_per_octave)))
+            .take_while(|f| *f <= nyquist)
+            .collect();
+
+        let kernels = frequencies
+            .iter()
+            .map(|f| Self::kernel(extractor, (q * sample_rate / *f).ceil(), q))
+            .collect();
+
+        let step = f_min / idx(Self::FIRST_OCTAVE_SAMPLES);
+        let last = *frequencies.last().unwrap();
+        let resampling = (0..)
+            .map(|j| f_min + step * idx(j))
+            .take_while(|f| *f <= last)
+            .map(|f| {
+                let pos = (f / f_min).log2() * idx(bins_per_octave);
+                let lower = pos.floor().to_usize().unwrap().min(frequencies.len() - 1);
+
+                (lower, pos - idx(lower))
+            })
+            .collect();
+
+        Self {
+            win_len,
+            n_ceps,
+            frequencies,
+            kernels,
+            resampling,
+        }
+    }
+
+    fn kernel(extractor: &CepstrumExtractor<T>, len: T, q: T) -> Kernel<T> {
+        let win_len = extractor.win_len();
+        let n_k = len.to_usize().unwrap();
+        assert!(
+            n_k <= win_len,
+            "Window too short for the lowest bin: {} < {}",
+            win_len,
+            n_k
+        );
+
+        let offset = (win_len - n_k) / 2;
+        let mut temporal = vec![Complex::zero(); win_len];
+        for n in 0..n_k {
+            let phase = T::TAU() * idx(n) / len;
+            let hann = cst::<T>(0.5) * (T::one() - phase.cos());
+
+            temporal[offset + n] = Complex::from_polar(hann / len, q * phase);
+        }
+
+        extractor.acquire().fft_mut(&mut temporal);
+
+        let spectral: Vec<Complex<T>> = temporal
+            .iter()
+            .map(|c| c.conj() / idx::<T>(win_len))
+            .collect();
+        let threshold = spectral.iter().fold(T::zero(), |acc, c| acc.max(c.norm()))
+            * cst(Self::KERNEL_THRESHOLD);
+
+        let start = spectral
+            .iter()
+            .position(|c| c.norm() >= threshold)
+            .unwrap_or(0);
+        let end = spectral
+            .iter()
+            .rposition(|c| c.norm() >= threshold)
+            .unwrap_or(0)
+            + 1;
+
+        Kernel {
+            start,
+            coeffs: spectral[start..end].to_vec(),
+        }
+    }
+
+    /// Centre frequencies of the constant-Q bins.
+    pub fn frequencies(&self) -> &[T] {
+        &self.frequencies
+    }
+
+    /// Computes the constant-Q transform of `frame`, one value per frequency in
+    /// [`Self::frequencies`].
+    pub fn cqt(&self, extractor: &CepstrumExtractor<T>, frame: &[Complex<T>]) -> Vec<Complex<T>> {
+        self.cqt_with_guard(&mut extractor.default_instance(), frame)
+    }
+
+    /// As [`Self::cqt`], but uses the extractor instance at index `instance`.
+    pub fn cqt_with_instance(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        frame: &[Complex<T>],
+        instance: usize,
+    ) -> Vec<Complex<T>> {
+        self.cqt_with_guard(&mut extractor.checkout(instance), frame)
+    }
+
+    /// As [`Self::cqt`], but uses the extractor instance held by `guard`.
+    pub fn cqt_with_guard(
+        &self,
+        guard: &mut InstanceGuard<'_, T>,
+        frame: &[Complex<T>],
+    ) -> Vec<Complex<T>> {
+        assert_eq!(
+            frame.len(),
+            self.win_len,
+            "Frame length differs from the analyser's one"
+        );
+        assert_eq!(
+            guard.win_len(),
+            self.win_len,
+            "Extractor length differs from the analyser's one"
+        );
+
+        let mut spectrum = frame.to_vec();
+        guard.fft_mut(&mut spectrum);
+
+        self.kernels
+            .iter()
+            .map(|k| {
+                spectrum[k.start..k.start + k.coeffs.len()]
+                    .iter()
+                    .zip(k.coeffs.iter())
+                    .fold(Complex::zero(), |acc, (x, k)| acc + x * k)
+            })
+            .collect()
+    }
+
+    /// Computes the CQCCs of `frame`, which should be already windowed.
+    pub fn compute(&self, extractor: &CepstrumExtractor<T>, frame: &[Complex<T>]) -> Vec<T> {
+        self.compute_with_guard(&mut extractor.default_instance(), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance at index `instance`.
+    pub fn compute_with_instance(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        frame: &[Complex<T>],
+        instance: usize,
+    ) -> Vec<T> {
+        self.compute_with_guard(&mut extractor.checkout(instance), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance held by `guard`.
+    pub fn compute_with_guard(
+        &self,
+        guard: &mut InstanceGuard<'_, T>,
+        frame: &[Complex<T>],
+    ) -> Vec<T> {
+        let cqt = self.cqt_with_guard(guard, frame);
+        let mut log_power = vec![T::zero(); cqt.len()];
+        T::log_power(&cqt, &mut log_power);
+
+        let last = log_power.len() - 1;
+        let uniform: Vec<T> = self
+            .resampling
+            .iter()
+            .map(|(lower, w)| {
+                log_power[*lower] * (T::one() - *w) + log_power[(*lower + 1).min(last)] * *w
+            })
+            .collect();
+
+        dct2(&uniform, self.n_ceps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cqcc;
+    use crate::CepstrumExtractor;
+    use crate::num_complex::Complex;
+
+    const WIN_LEN: usize = 2048;
+    const SAMPLE_RATE: f64 = 8000.;
+
+    #[test]
+    fn cqt_peak() {
+        let extractor = CepstrumExtractor::new(WIN_LEN);
+        let cqcc = Cqcc::new(&extractor, SAMPLE_RATE, 110., 12, 20);
+
+        let bin = 19;
+        let f = cqcc.frequencies()[bin];
+        let frame: Vec<Complex<f64>> = (0..WIN_LEN)
+            .map(|n| Complex::from((std::f64::consts::TAU * f * n as f64 / SAMPLE_RATE).sin()))
+            .collect();
+
+        let cqt = cqcc.cqt(&extractor, &frame);
+        let max = cqt
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.norm().total_cmp(&b.norm()))
+            .unwrap();
+
+        assert_eq!(max.0, bin);
+        assert_eq!(cqcc.compute(&extractor, &frame).len(), 20);
+    }
+}
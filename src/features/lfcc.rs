@@ -0,0 +1,142 @@
+//! Linear-frequency cepstral coefficients (LFCC) module.
+//!
+//! More information at:
+//! - M. Sahidullah, T. Kinnunen, C. Hanilçi, "A comparison of features for synthetic speech
+//!   detection", Interspeech 2015.
+
+use alloc::vec::Vec;
+
+use crate::dsp::{dct2, idx, triangular_filterbank};
+use crate::num_complex::Complex;
+use crate::{CepFloat, CepstrumExtractor, InstanceGuard};
+
+/// LFCC analyser: log energies of a linearly spaced triangular filterbank, decorrelated with a DCT.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::features::lfcc::Lfcc;
+/// use cepstrum_extractor::windows::hamming::Hamming;
+///
+/// const WIN_LEN: usize = 512;
+///
+/// let extractor = CepstrumExtractor::new(WIN_LEN);
+/// let lfcc = Lfcc::new(WIN_LEN, 16_000., 20, 20);
+///
+/// let ceps = lfcc.compute(&extractor, &[0.1f32; WIN_LEN].hamming_complex());
+/// assert_eq!(ceps.len(), 20);
+/// ```
+pub struct Lfcc<T: CepFloat> {
+    win_len: usize,
+    n_ceps: usize,
+    filters: Vec<Vec<T>>,
+}
+
+impl<T: CepFloat> Lfcc<T> {
+    /// Builds a new LFCC analyser for frames long `win_len`, sampled at `sample_rate`, with
+    /// `n_filters` filters spanning `0..sample_rate / 2` and returning `n_ceps` coefficients.
+    pub fn new(win_len: usize, sample_rate: T, n_filters: usize, n_ceps: usize) -> Self {
+        Self::with_band(
+            win_len,
+            sample_rate,
+            n_filters,
+            n_ceps,
+            T::zero(),
+            sample_rate / idx(2),
+        )
+    }
+
+    /// As [`Self::new`], but the filters span `f_min..f_max` (in Hz).
+    pub fn with_band(
+        win_len: usize,
+        sample_rate: T,
+        n_filters: usize,
+        n_ceps: usize,
+        f_min: T,
+        f_max: T,
+    ) -> Self {
+        assert!(f_min < f_max, "Empty band: {:?}..{:?}", f_min, f_max);
+        assert!(n_ceps <= n_filters, "More coefficients than filters");
+
+        let step = (f_max - f_min) / idx(n_filters + 1);
+        let edges: Vec<T> = (0..n_filters + 2).map(|i| f_min + step * idx(i)).collect();
+
+        Self {
+            win_len,
+            n_ceps,
+            filters: triangular_filterbank(&edges, win_len, sample_rate),
+        }
+    }
+
+    /// Computes the LFCCs of `frame`, which should be already windowed.
+    pub fn compute(&self, extractor: &CepstrumExtractor<T>, frame: &[Complex<T>]) -> Vec<T> {
+        self.compute_with_guard(&mut extractor.default_instance(), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance at index `instance`.
+    pub fn compute_with_instance(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        frame: &[Complex<T>],
+        instance: usize,
+    ) -> Vec<T> {
+        self.compute_with_guard(&mut extractor.checkout(instance), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance held by `guard`.
+    pub fn compute_with_guard(
+        &self,
+        guard: &mut InstanceGuard<'_, T>,
+        frame: &[Complex<T>],
+    ) -> Vec<T> {
+        assert_eq!(
+            frame.len(),
+            self.win_len,
+            "Frame length differs from the analyser's one"
+        );
+        assert_eq!(
+            guard.win_len(),
+            self.win_len,
+            "Extractor length differs from the analyser's one"
+        );
+
+        let power = guard.power_spectrum(frame);
+
+        let energies: Vec<T> = self
+            .filters
+            .iter()
+            .map(|f| {
+                f.iter()
+                    .zip(power.iter())
+                    .fold(T::zero(), |acc, (w, p)| acc + *w * *p)
+                    .max(T::min_positive_value())
+                    .ln()
+            })
+            .collect();
+
+        dct2(&energies, self.n_ceps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lfcc;
+    use crate::CepstrumExtractor;
+    use crate::num_complex::Complex;
+    use crate::num_traits::Zero;
+
+    const WIN_LEN: usize = 512;
+
+    #[test]
+    fn flat_spectrum() {
+        let extractor = CepstrumExtractor::new(WIN_LEN);
+        let lfcc = Lfcc::new(WIN_LEN, 16_000., 20, 13);
+
+        let mut impulse = vec![Complex::<f64>::zero(); WIN_LEN];
+        impulse[0] = Complex::from(1.);
+
+        let ceps = lfcc.compute(&extractor, &impulse);
+
+        assert!(ceps[1..].iter().all(|c| c.abs() < 0.1), "{:?}", ceps);
+    }
+}
@@ -0,0 +1,5 @@
+//! Cepstral front ends built on top of [`crate::CepstrumExtractor`].
+
+pub mod cqcc;
+pub mod lfcc;
+pub mod plp;
@@ -0,0 +1,344 @@
+//! Perceptual linear prediction (PLP) and RASTA-PLP module.
+//!
+//! More information at:
+//! - H. Hermansky, "Perceptual linear predictive (PLP) analysis of speech", JASA 1990.
+//! - H. Hermansky, N. Morgan, "RASTA processing of speech", IEEE TSAP 1994.
+//! - <https://www.ee.columbia.edu/~dpwe/resources/matlab/rastamat/>
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::dsp::{cst, idx, levinson, lpc_to_cepstrum};
+use crate::num_complex::Complex;
+use crate::{CepFloat, CepstrumExtractor, InstanceGuard};
+
+/// Converts a frequency in Hz into the Bark scale.
+#[inline]
+pub fn hz_to_bark<T: CepFloat>(hz: T) -> T {
+    cst::<T>(6.) * (hz / cst(600.)).asinh()
+}
+
+/// Converts a value in the Bark scale into Hz.
+#[inline]
+pub fn bark_to_hz<T: CepFloat>(bark: T) -> T {
+    cst::<T>(600.) * (bark / cst(6.)).sinh()
+}
+
+/// Band-pass filter applied to the log critical-band trajectories, i.e. along time, one band at a
+/// time.
+struct Rasta<T: CepFloat> {
+    pole: T,
+    frames: usize,
+    /// Last four inputs of every band, most recent first.
+    inputs: Vec<[T; 4]>,
+    outputs: Vec<T>,
+}
+
+impl<T: CepFloat> Rasta<T> {
+    /// Numerator of the filter, `-(-2..=2) / 10`.
+    const NUMER: [f64; 5] = [0.2, 0.1, 0., -0.1, -0.2];
+
+    fn new(bands: usize, pole: T) -> Self {
+        Self {
+            pole,
+            frames: 0,
+            inputs: vec![[T::zero(); 4]; bands],
+            outputs: vec![T::zero(); bands],
+        }
+    }
+
+    fn reset(&mut self) {
+        self.frames = 0;
+        self.inputs.iter_mut().for_each(|i| *i = [T::zero(); 4]);
+        self.outputs.iter_mut().for_each(|o| *o = T::zero());
+    }
+
+    /// Filters one frame of critical-band energies, in place.
+    ///
+    /// As in the reference implementation, the first four frames only prime the filter and yield
+    /// a flat (zero in the log domain) output.
+    fn process(&mut self, bands: &mut [T]) {
+        for ((band, x), y) in bands
+            .iter_mut()
+            .zip(self.inputs.iter_mut())
+            .zip(self.outputs.iter_mut())
+        {
+            let log = band.max(T::min_positive_value()).ln();
+
+            *y = if self.frames < 4 {
+                T::zero()
+            } else {
+                x.iter().zip(Self::NUMER[1..].iter()).fold(
+                    cst::<T>(Self::NUMER[0]) * log + self.pole * *y,
+                    |acc, (x, n)| acc + cst::<T>(*n) * *x,
+                )
+            };
+
+            x.rotate_right(1);
+            x[0] = log;
+
+            *band = y.exp();
+        }
+
+        self.frames += 1;
+    }
+}
+
+/// PLP analyser; computes `order + 1` cepstral coefficients per frame.
+///
+/// The spectrum of each frame is obtained through the extractor; it is integrated over a Bark
+/// filterbank, weighted by an equal-loudness curve, compressed with the intensity-to-loudness power
+/// law and finally approximated by an all-pole model of order `order`, whose cepstrum is returned.
+///
+/// If RASTA is enabled via [`Self::with_rasta`], the analyser keeps a state across frames, so frames
+/// must be passed in temporal order. [`Self::reset`] clears such a state.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::features::plp::Plp;
+/// use cepstrum_extractor::windows::hamming::Hamming;
+///
+/// const WIN_LEN: usize = 400;
+///
+/// let extractor = CepstrumExtractor::new(WIN_LEN);
+/// let mut plp = Plp::new(WIN_LEN, 16_000., 12).with_rasta(0.94);
+///
+/// let signal = vec![0f64; WIN_LEN * 10];
+/// for frame in signal.chunks_exact(WIN_LEN) {
+///     let ceps = plp.compute(&extractor, &frame.hamming_complex());
+///     assert_eq!(ceps.len(), 13);
+/// }
+/// ```
+pub struct Plp<T: CepFloat> {
+    win_len: usize,
+    order: usize,
+    weights: Vec<Vec<T>>,
+    loudness: Vec<T>,
+    rasta: Option<Rasta<T>>,
+}
+
+impl<T: CepFloat> Plp<T> {
+    /// Intensity-to-loudness compression exponent.
+    const COMPRESSION: f64 = 0.33;
+
+    /// Builds a new PLP analyser for frames long `win_len`, sampled at `sample_rate`, using an
+    /// all-pole model of order `order`.
+    pub fn new(win_len: usize, sample_rate: T, order: usize) -> Self {
+        assert!(win_len >= 2, "Window too short: {}", win_len);
+
+        let nyquist_bark = hz_to_bark(sample_rate / cst(2.));
+        let bands = nyquist_bark.ceil().to_usize().unwrap() + 1;
+        let step = nyquist_bark / idx(bands - 1);
+
+        let bin_barks: Vec<T> = (0..=win_len / 2)
+            .map(|k| hz_to_bark(idx::<T>(k) * sample_rate / idx(win_len)))
+            .collect();
+
+        let weights = (0..bands)
+            .map(|i| {
+                let mid = idx::<T>(i) * step;
+
+                bin_barks
+                    .iter()
+                    .map(|b| {
+                        let lof = *b - mid - cst(0.5);
+                        let hif = *b - mid + cst(0.5);
+
+                        cst::<T>(10.).powf(T::zero().min(hif.min(cst::<T>(-2.5) * lof)))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let loudness = (0..bands)
+            .map(|i| {
+                let fsq = bark_to_hz(idx::<T>(i) * step).powi(2);
+                let ftmp = fsq + cst(1.6e5);
+
+                (fsq / ftmp).powi(2) * ((fsq + cst(1.44e6)) / (fsq + cst(9.61e6)))
+            })
+            .collect();
+
+        Self {
+            win_len,
+            order,
+            weights,
+            loudness,
+            rasta: None,
+        }
+    }
+
+    /// Enables RASTA filtering of the log critical-band trajectories, using `pole` as the pole of
+    /// the filter (usually `0.94` or `0.98`).
+    pub fn with_rasta(mut self, pole: T) -> Self {
+        self.rasta = Some(Rasta::new(self.weights.len(), pole));
+        self
+    }
+
+    /// Number of critical bands of the filterbank.
+    pub fn bands(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Clears the RASTA state, if any.
+    pub fn reset(&mut self) {
+        if let Some(rasta) = &mut self.rasta {
+            rasta.reset();
+        }
+    }
+
+    /// Computes the PLP cepstrum of `frame`, which should be already windowed.
+    pub fn compute(&mut self, extractor: &CepstrumExtractor<T>, frame: &[Complex<T>]) -> Vec<T> {
+        self.compute_with_guard(&mut extractor.default_instance(), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance at index `instance`.
+    pub fn compute_with_instance(
+        &mut self,
+        extractor: &CepstrumExtractor<T>,
+        frame: &[Complex<T>],
+        instance: usize,
+    ) -> Vec<T> {
+        self.compute_with_guard(&mut extractor.checkout(instance), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance held by `guard`.
+    pub fn compute_with_guard(
+        &mut self,
+        guard: &mut InstanceGuard<'_, T>,
+        frame: &[Complex<T>],
+    ) -> Vec<T> {
+        assert_eq!(
+            frame.len(),
+            self.win_len,
+            "Frame length differs from the analyser's one"
+        );
+        assert_eq!(
+            guard.win_len(),
+            self.win_len,
+            "Extractor length differs from the analyser's one"
+        );
+
+        let power = guard.power_spectrum(frame);
+
+        let mut bands: Vec<T> = self
+            .weights
+            .iter()
+            .map(|w| {
+                w.iter()
+                    .zip(power.iter())
+                    .fold(T::zero(), |acc, (w, p)| acc + *w * *p)
+            })
+            .collect();
+
+        if let Some(rasta) = &mut self.rasta {
+            rasta.process(&mut bands);
+        }
+
+        for (band, l) in bands.iter_mut().zip(self.loudness.iter()) {
+            *band = (*band * *l).powf(cst(Self::COMPRESSION));
+        }
+
+        // Edge bands are unreliable (the loudness curve is zero at DC): replicate the neighbours.
+        let n = bands.len();
+        if n > 2 {
+            bands[0] = bands[1];
+            bands[n - 1] = bands[n - 2];
+        }
+
+        let (a, err) = levinson(&Self::autocorrelation(&bands, self.order), self.order);
+
+        lpc_to_cepstrum(&a, err, self.order + 1)
+    }
+
+    /// Autocorrelation of the auditory spectrum, i.e. the inverse DFT of the symmetric power
+    /// spectrum described by `bands`.
+    fn autocorrelation(bands: &[T], order: usize) -> Vec<T> {
+        let n = bands.len();
+        let last = idx::<T>(n.max(2) - 1);
+
+        (0..=order)
+            .map(|k| {
+                bands.iter().enumerate().fold(T::zero(), |acc, (i, p)| {
+                    let w = if i == 0 || i == n - 1 {
+                        T::one()
+                    } else {
+                        cst(2.)
+                    };
+                    acc + w * *p * (T::PI() * idx(k) * idx(i) / last).cos()
+                }) / (cst::<T>(2.) * last)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Plp;
+    use crate::CepstrumExtractor;
+    use crate::num_complex::Complex;
+    use crate::windows::hamming::Hamming;
+
+    const WIN_LEN: usize = 256;
+    const FRAMES: usize = 200;
+
+    fn noise(len: usize) -> Vec<f64> {
+        let mut state = 0x2545F491u64;
+        (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 33) as f64 / (1u64 << 31) as f64 - 0.5
+            })
+            .collect()
+    }
+
+    /// Applies a fixed channel by multiplying the spectrum, i.e. through circular convolution.
+    fn channel(frame: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        let len = frame.len();
+        (0..len)
+            .map(|i| frame[i] + frame[(i + len - 1) % len] * 0.6)
+            .collect()
+    }
+
+    fn distance(plp: &mut Plp<f64>, extractor: &CepstrumExtractor<f64>) -> f64 {
+        let signal = noise(WIN_LEN * FRAMES);
+
+        let clean: Vec<Vec<f64>> = signal
+            .chunks_exact(WIN_LEN)
+            .map(|f| plp.compute(extractor, &f.hamming_complex()))
+            .collect();
+        plp.reset();
+        let filtered: Vec<Vec<f64>> = signal
+            .chunks_exact(WIN_LEN)
+            .map(|f| plp.compute(extractor, &channel(&f.hamming_complex())))
+            .collect();
+
+        clean[FRAMES / 2..]
+            .iter()
+            .zip(filtered[FRAMES / 2..].iter())
+            .map(|(a, b)| {
+                a[1..]
+                    .iter()
+                    .zip(b[1..].iter())
+                    .map(|(a, b)| (a - b).powi(2))
+                    .sum::<f64>()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn rasta_removes_channel() {
+        let extractor = CepstrumExtractor::new(WIN_LEN);
+
+        let plain = distance(&mut Plp::new(WIN_LEN, 8000., 8), &extractor);
+        let rasta = distance(
+            &mut Plp::new(WIN_LEN, 8000., 8).with_rasta(0.94),
+            &extractor,
+        );
+
+        assert!(rasta < plain * 0.25, "{} vs {}", rasta, plain);
+    }
+}
@@ -1,125 +1,327 @@
-use std::{iter, ptr};
-use std::sync::{Arc, Mutex};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::cell::Cell;
 
-use rustfft::{Fft, FftPlanner};
-use rustfft::num_complex::Complex;
-use rustfft::num_traits::Zero;
+#[cfg(feature = "std")]
+use arc_swap::ArcSwap;
+#[cfg(not(feature = "std"))]
+use spin::RwLock;
+use crate::num_complex::Complex;
+#[cfg(feature = "std")]
+use thread_local::ThreadLocal;
 use crate::CepFloat;
+use crate::backend::FftBackend;
+use crate::plan::PlanCache;
+use crate::slots::Slots;
 
-pub struct CepFft<T: CepFloat> {
-    len: usize,
+/// Backends of the lengths served by an extractor.
+pub struct Plans<T: CepFloat> {
+    /// The first one is the main one, used for any other length.
+    backends: Vec<Arc<dyn FftBackend<T>>>,
+    /// Length of the scratch fitting all the backends.
     scratch_len: usize,
-    scratches: Mutex<Vec<Vec<Complex<T>>>>,
+    /// Length of the longest backend, i.e. of the buffer used by out-of-place computations.
+    work_len: usize,
+}
+
+impl<T: CepFloat> Plans<T> {
+    fn new(backends: Vec<Arc<dyn FftBackend<T>>>) -> Self {
+        Self {
+            scratch_len: backends.iter().map(|b| b.scratch_len()).max().unwrap(),
+            work_len: backends.iter().map(|b| b.len()).max().unwrap(),
+            backends,
+        }
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.backends[0].len()
+    }
+
+    #[inline(always)]
+    pub fn scratch_len(&self) -> usize {
+        self.scratch_len
+    }
+
+    #[inline(always)]
+    pub fn work_len(&self) -> usize {
+        self.work_len
+    }
+
+    /// Whether inputs long `len` are transformed as a whole.
+    #[inline]
+    pub fn serves(&self, len: usize) -> bool {
+        self.backends.iter().any(|b| b.len() == len)
+    }
+
+    /// Backend for inputs long `len`.
+    #[inline]
+    pub fn get(&self, len: usize) -> &Arc<dyn FftBackend<T>> {
+        self.backends
+            .iter()
+            .find(|b| b.len() == len)
+            .unwrap_or(&self.backends[0])
+    }
+
+    /// Same plans, with `backend` in place of the one of the same length (or of the main one, if
+    /// `main` is set).
+    fn with(&self, backend: Arc<dyn FftBackend<T>>, main: bool) -> Self {
+        let mut backends = self.backends.clone();
+
+        match backends.iter().position(|b| b.len() == backend.len()) {
+            Some(i) if i != 0 && main => {
+                backends.remove(i);
+                backends[0] = backend;
+            }
+            Some(i) => backends[i] = backend,
+            None if main => backends[0] = backend,
+            None => backends.push(backend),
+        }
+
+        Self::new(backends)
+    }
+}
+
+pub struct CepFft<T: CepFloat> {
+    cache: Arc<PlanCache<T>>,
+    /// Swapped as a whole when lengths change; checked out instances keep the previous ones.
+    #[cfg(feature = "std")]
+    plans: ArcSwap<Plans<T>>,
+    /// Swapped as a whole when lengths change; checked out instances keep the previous ones.
+    #[cfg(not(feature = "std"))]
+    plans: RwLock<Arc<Plans<T>>>,
 
-    fft_instance: Arc<dyn Fft<T>>,
-    ifft_instance: Arc<dyn Fft<T>>
+    /// Scratch of every instance used by index, resized when checked out.
+    scratches: Slots<Vec<Complex<T>>>,
+    /// Scratch of every instance checked out without an index, kept apart from the ones used by
+    /// index so that the two ways never contend for the same instance.
+    pool: Slots<Vec<Complex<T>>>,
+    /// Scratch of every thread, if enabled; empty while checked out.
+    #[cfg(feature = "std")]
+    local_scratches: Option<ThreadLocal<Cell<Vec<Complex<T>>>>>,
 }
 
 impl<T: CepFloat> CepFft<T> {
     pub fn new(len: usize) -> CepFft<T> {
-        let mut fft_planner = FftPlanner::<T>::new();
+        Self::with_cache(len, PlanCache::global())
+    }
 
-        let fft_instance = fft_planner.plan_fft_forward(len);
+    pub fn with_cache(len: usize, cache: Arc<PlanCache<T>>) -> CepFft<T> {
+        let plan = cache.plan(len);
+        Self::with_backend(plan, cache)
+    }
 
+    /// Uses `backend` as main plan; other lengths are planned with `cache`.
+    pub fn with_backend(backend: Arc<dyn FftBackend<T>>, cache: Arc<PlanCache<T>>) -> CepFft<T> {
         CepFft {
-            len,
-            scratch_len: fft_instance.get_inplace_scratch_len(),
-            scratches: Mutex::new(vec![vec![Complex::zero(); fft_instance.get_inplace_scratch_len()]]),
-
-            fft_instance,
-            ifft_instance: fft_planner.plan_fft_inverse(len),
+            cache,
+            #[cfg(feature = "std")]
+            plans: ArcSwap::from_pointee(Plans::new(vec![backend])),
+            #[cfg(not(feature = "std"))]
+            plans: RwLock::new(Arc::new(Plans::new(vec![backend]))),
+            scratches: Slots::new(1),
+            pool: Slots::new(1),
+            #[cfg(feature = "std")]
+            local_scratches: None,
         }
     }
 
-    pub fn set_len(&mut self, len: usize) {
-        if len == self.len {
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.plans().len()
+    }
+
+    /// Current plans, owned so that they can be kept by an instance for as long as it is checked
+    /// out.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn plans(&self) -> Arc<Plans<T>> {
+        self.plans.load_full()
+    }
+
+    /// Current plans, owned so that they can be kept by an instance for as long as it is checked
+    /// out.
+    #[cfg(not(feature = "std"))]
+    #[inline]
+    pub fn plans(&self) -> Arc<Plans<T>> {
+        self.plans.read().clone()
+    }
+
+    /// Replaces the current plans with the ones returned by `f`.
+    #[cfg(feature = "std")]
+    fn update(&self, f: impl Fn(&Plans<T>) -> Plans<T>) {
+        self.plans.rcu(|plans| f(plans));
+    }
+
+    /// Replaces the current plans with the ones returned by `f`.
+    #[cfg(not(feature = "std"))]
+    fn update(&self, f: impl Fn(&Plans<T>) -> Plans<T>) {
+        let mut plans = self.plans.write();
+        *plans = Arc::new(f(&plans));
+    }
+
+    pub fn set_len(&self, len: usize) {
+        if len == self.len() {
             return;
         }
 
-        let mut fft_planner = FftPlanner::<T>::new();
-
-        self.fft_instance = fft_planner.plan_fft_forward(len);
-        self.ifft_instance = fft_planner.plan_fft_inverse(len);
+        let backend = self.cache.plan(len);
+        self.update(|plans| plans.with(backend.clone(), true));
+    }
 
-        let new_scratches_len = self.fft_instance.get_inplace_scratch_len();
+    /// Plan of the FFTs of length `len`, from the cache of the extractor.
+    pub fn plan(&self, len: usize) -> Arc<dyn FftBackend<T>> {
+        self.cache.plan(len)
+    }
 
-        for scratch in self.scratches.lock().unwrap().iter_mut() {
-            if new_scratches_len > scratch.len() {
-                scratch.extend(iter::repeat(Complex::zero()).take(new_scratches_len - scratch.len()));
-            } else {
-                scratch.truncate(new_scratches_len);
-            }
+    /// Serves inputs long `len` with a plan of their own.
+    pub fn add_len(&self, len: usize) {
+        if self.plans().serves(len) {
+            return;
         }
 
-        self.scratch_len = new_scratches_len;
-        self.len = len;
+        self.add_backend(self.cache.plan(len));
     }
 
+    /// Serves inputs long as `backend` with it, in place of any plan of the same length.
+    pub fn add_backend(&self, backend: Arc<dyn FftBackend<T>>) {
+        self.update(|plans| plans.with(backend.clone(), false));
+    }
+
+    /// Increases the number of instances, both by index and in the pool, to `new_count`.
     pub fn extend_scratches(&self, new_count: usize) {
-        let mut s = self.scratches.lock().unwrap();
-        let len = s.len();
+        self.scratches.extend(new_count);
+        self.pool.extend(new_count);
+    }
+
+    /// Enables the scratches of the single threads.
+    #[cfg(feature = "std")]
+    pub fn enable_local_scratches(&mut self) {
+        self.local_scratches.get_or_insert_with(ThreadLocal::new);
+    }
+
+    /// Takes the scratch of the calling thread, creating it if needed. Returns `None` if the
+    /// scratches of the single threads are not enabled.
+    #[cfg(feature = "std")]
+    pub fn checkout_local(&self) -> Option<Vec<Complex<T>>> {
+        // Empty the first time, or if the scratch is already checked out by the same thread.
+        Some(self.local_scratches.as_ref()?.get_or_default().take())
+    }
 
-        if new_count > s.len() {
-            s.extend(
-                iter::repeat(vec![Complex::zero(); self.scratch_len]).take(new_count - len)
-            )
+    /// Without the standard library there are no scratches of the single threads.
+    #[cfg(not(feature = "std"))]
+    pub fn checkout_local(&self) -> Option<Vec<Complex<T>>> {
+        None
+    }
+
+    /// Gives back the scratch of the calling thread.
+    #[cfg(feature = "std")]
+    pub fn checkin_local(&self, scratch: Vec<Complex<T>>) {
+        if let Some(local) = &self.local_scratches {
+            local.get_or_default().set(scratch);
         }
     }
 
-    #[inline(always)]
-    fn retrieve_scratch(&self, i: usize) -> &mut [Complex<T>] {
-        let mut scratches = self.scratches.lock().unwrap();
-        assert!(i < scratches.len(), "Index out of bounds: {}", i);
-
-        unsafe {
-            &mut *ptr::slice_from_raw_parts_mut(
-                scratches.get_unchecked_mut(i).as_mut_ptr(),
-                self.scratch_len
-            )
+    /// Without the standard library there are no scratches of the single threads.
+    #[cfg(not(feature = "std"))]
+    pub fn checkin_local(&self, _scratch: Vec<Complex<T>>) {}
+
+    /// Takes the scratch of instance `i`, which must not be in use.
+    pub fn checkout(&self, i: usize) -> Vec<Complex<T>> {
+        match self.scratches.take(i) {
+            Some(Some(scratch)) => scratch,
+            Some(None) => panic!("Instance {} is already in use", i),
+            None => panic!("Index out of bounds: {}", i),
         }
     }
 
-    #[inline]
-    pub fn do_fft(&self, input: &mut [Complex<T>], i: usize) {
-        self.fft_instance.process_with_scratch(
-            input,
-            self.retrieve_scratch(i)
-        );
+    /// Takes the scratch of the first instance of the pool not in use, together with its index.
+    /// If all of them are in use, a new instance is added when `grow` is set, otherwise `None` is
+    /// returned.
+    pub fn checkout_any(&self, grow: bool) -> Option<(usize, Vec<Complex<T>>)> {
+        loop {
+            if let Some((i, scratch)) = self.pool.take_any() {
+                return Some((i, scratch));
+            }
+            if !grow {
+                return None;
+            }
+
+            // Another thread may take the new instance first; then, look for another one.
+            self.pool.push();
+        }
     }
 
-    #[inline]
-    pub fn do_ifft(&self, input: &mut [Complex<T>], i: usize) {
-        self.ifft_instance.process_with_scratch(
-            input,
-            self.retrieve_scratch(i)
-        );
+    /// Gives back the scratch of instance `i`, taken from the pool if `pooled` is set.
+    ///
+    /// # Safety
+    /// Instance `i` must have been checked out by the caller.
+    pub unsafe fn checkin(&self, i: usize, pooled: bool, scratch: Vec<Complex<T>>) {
+        let slots = if pooled { &self.pool } else { &self.scratches };
+        unsafe { slots.put(i, scratch) };
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::num_complex::Complex;
+
     use super::CepFft;
+    use crate::guard::{InstanceGuard, Source};
 
     const LEN: usize = 10;
 
     #[test]
     fn check_scratches() {
-        let mut inst: CepFft<f32> = CepFft::new(LEN);
+        let inst: CepFft<f32> = CepFft::new(LEN);
 
-        assert_eq!(inst.scratches.lock().unwrap().len(), 1);
+        assert_eq!(inst.scratches.len(), 1);
 
         inst.extend_scratches(10);
 
-        assert_eq!(inst.scratches.lock().unwrap().len(), 10);
+        assert_eq!(inst.scratches.len(), 10);
 
         inst.extend_scratches(9);
 
-        assert_eq!(inst.scratches.lock().unwrap().len(), 10);
+        assert_eq!(inst.scratches.len(), 10);
 
-        assert!(inst.scratches.lock().unwrap().iter().all(|s| s.len() == inst.scratch_len && s.len() == LEN));
+        let guard = InstanceGuard::new(&inst, Source::Indexed(9), inst.checkout(9));
+        assert!(guard.scratch_len() == inst.plans().scratch_len() && guard.scratch_len() == LEN);
 
         inst.set_len(LEN * 2);
 
-        assert!(inst.scratches.lock().unwrap().iter().all(|s| s.len() == inst.scratch_len && s.len() == LEN * 2));
+        // A guard keeps the plans it was created with.
+        assert_eq!(guard.win_len(), LEN);
+        drop(guard);
+
+        let guard = InstanceGuard::new(&inst, Source::Indexed(9), inst.checkout(9));
+        assert!(guard.scratch_len() == inst.plans().scratch_len() && guard.scratch_len() == LEN * 2);
+        assert_eq!(guard.win_len(), LEN * 2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn multiple_lengths() {
+        let inst: CepFft<f64> = CepFft::new(LEN);
+        inst.add_len(LEN * 4);
+        let mut guard = InstanceGuard::new(&inst, Source::Indexed(0), inst.checkout(0));
+
+        let signal: Vec<Complex<f64>> = (0..LEN * 4).map(|i| Complex::from((i % 7) as f64)).collect();
+
+        // An input long as an additional length is transformed as a whole...
+        let mut whole = signal.clone();
+        guard.fft_mut(&mut whole);
+        let mut expected = signal.clone();
+        let other = CepFft::new(LEN * 4);
+        InstanceGuard::new(&other, Source::Indexed(0), other.checkout(0)).fft_mut(&mut expected);
+        assert_eq!(whole, expected);
+
+        // ...while any other is split into windows of the main length.
+        let mut chunked = signal[..LEN * 2].to_vec();
+        guard.fft_mut(&mut chunked);
+        let mut first = signal[..LEN].to_vec();
+        guard.fft_mut(&mut first);
+        assert_eq!(chunked[..LEN], first[..]);
+    }
+}
@@ -0,0 +1,186 @@
+//! Module containing the extractor of a length fixed at compile time.
+
+use core::slice;
+
+use crate::CepFloat;
+use crate::backend::radix2;
+use crate::num_complex::Complex;
+
+/// Extractor of windows long `N`, known at compile time, with its twiddles stored inline: it never
+/// allocates, so it suits embedded targets and real-time threads.
+///
+/// FFTs are computed as by [`crate::backend::Radix2Backend`], so `N` must be a power of two; any
+/// other length fails to compile. There are no instances to manage: every method takes `&self`,
+/// so a single extractor can be shared by any number of threads. The `*_into` methods keep their
+/// buffer of `N` complex values on the stack.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::FixedCepstrumExtractor;
+///
+/// let extractor: FixedCepstrumExtractor<f32, 128> = FixedCepstrumExtractor::new();
+/// let signal = [1f32; 128];
+/// let mut ceps = [0f32; 64];
+///
+/// extractor.rceps_into(&signal, &mut ceps);
+/// ```
+///
+/// ```rust,compile_fail
+/// use cepstrum_extractor::FixedCepstrumExtractor;
+///
+/// // 100 is not a power of two.
+/// let extractor: FixedCepstrumExtractor<f32, 100> = FixedCepstrumExtractor::new();
+/// ```
+pub struct FixedCepstrumExtractor<T: CepFloat, const N: usize> {
+    /// Real and imaginary parts of `e^(-2πin/N)` for `n` in `0..N / 2`, one after the other: an
+    /// array of `N / 2` complex values cannot be declared with a const generic length.
+    twiddles: [T; N],
+}
+
+impl<T: CepFloat, const N: usize> FixedCepstrumExtractor<T, N> {
+    /// Evaluated when the extractor is built, failing the compilation for wrong lengths.
+    const VALID_LEN: () = assert!(N.is_power_of_two(), "Length must be a power of two");
+
+    /// Builds a new extractor.
+    pub fn new() -> Self {
+        let () = Self::VALID_LEN;
+
+        let step = -(T::PI() + T::PI()) / T::from(N).unwrap();
+
+        Self {
+            twiddles: core::array::from_fn(|i| {
+                let twiddle = Complex::from_polar(T::one(), step * T::from(i / 2).unwrap());
+                if i % 2 == 0 { twiddle.re } else { twiddle.im }
+            }),
+        }
+    }
+
+    /// The twiddles, as complex values.
+    fn twiddles(&self) -> &[Complex<T>] {
+        // SAFETY: `Complex<T>` is `#[repr(C)]`, i.e. laid out as two consecutive `T`.
+        unsafe { slice::from_raw_parts(self.twiddles.as_ptr() as *const Complex<T>, N / 2) }
+    }
+
+    fn _ceps_mut(&self, signal: &mut [Complex<T>; N], f: fn(&mut [Complex<T>])) {
+        radix2(signal, self.twiddles(), false);
+        f(signal);
+        radix2(signal, self.twiddles(), true);
+    }
+
+    fn _ceps_into<S: Copy + Into<Complex<T>>>(
+        &self,
+        signal: &[S; N],
+        out: &mut [T],
+        f: fn(&mut [Complex<T>]),
+    ) {
+        assert_eq!(
+            out.len(),
+            N / 2,
+            "Output length differs from half the signal's one"
+        );
+
+        let mut work: [Complex<T>; N] = core::array::from_fn(|i| signal[i].into());
+        self._ceps_mut(&mut work, f);
+
+        out.iter_mut().zip(work.iter()).for_each(|(o, w)| *o = w.re);
+    }
+
+    /// As [`crate::CepstrumExtractor::rceps_mut`].
+    ///
+    /// <div class="warning">
+    ///
+    /// As with spectrums, the meaningful area will be `signal[0..N / 2]`.
+    /// </div>
+    pub fn rceps_mut(&self, signal: &mut [Complex<T>; N]) {
+        self._ceps_mut(signal, T::log_magnitude)
+    }
+
+    /// As [`crate::CepstrumExtractor::rceps_into`]; `out` must be long `N / 2`.
+    pub fn rceps_into<S: Copy + Into<Complex<T>>>(&self, signal: &[S; N], out: &mut [T]) {
+        self._ceps_into(signal, out, T::log_magnitude)
+    }
+
+    /// As [`crate::CepstrumExtractor::cceps_mut`].
+    ///
+    /// <div class="warning">
+    ///
+    /// As with spectrums, the meaningful area will be `signal[0..N / 2]`.
+    /// </div>
+    pub fn cceps_mut(&self, signal: &mut [Complex<T>; N]) {
+        self._ceps_mut(signal, T::complex_log)
+    }
+
+    /// As [`crate::CepstrumExtractor::cceps_into`]; `out` must be long `N / 2`.
+    pub fn cceps_into<S: Copy + Into<Complex<T>>>(&self, signal: &[S; N], out: &mut [T]) {
+        self._ceps_into(signal, out, T::complex_log)
+    }
+}
+
+impl<T: CepFloat, const N: usize> Default for FixedCepstrumExtractor<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FixedCepstrumExtractor;
+    use crate::num_complex::Complex;
+    use crate::{CepstrumExtractor, RealToComplex};
+
+    const LEN: usize = 64;
+
+    #[test]
+    fn same_as_extractor() {
+        let signal: [f64; LEN] =
+            core::array::from_fn(|i| (i as f64 * 0.4).sin() + ((i * 7919) % 127) as f64 / 127.);
+
+        let fixed: FixedCepstrumExtractor<f64, LEN> = FixedCepstrumExtractor::new();
+        let extractor: CepstrumExtractor<f64> = CepstrumExtractor::new(LEN);
+
+        let close =
+            |a: &[f64], b: &[Complex<f64>]| a.iter().zip(b).all(|(a, b)| (a - b.re).abs() < 1e-9);
+
+        let mut out = [0.; LEN / 2];
+        fixed.rceps_into(&signal, &mut out);
+        assert!(close(
+            &out,
+            &extractor.rceps_to_vec(&signal.to_complex_vec())
+        ));
+
+        fixed.cceps_into(&signal, &mut out);
+        assert!(close(
+            &out,
+            &extractor.cceps_to_vec(&signal.to_complex_vec())
+        ));
+
+        let mut complex: [Complex<f64>; LEN] = core::array::from_fn(|i| signal[i].into());
+        fixed.rceps_mut(&mut complex);
+        let expected = extractor.rceps_to_vec(&signal.to_complex_vec());
+        assert!(
+            complex[..LEN / 2]
+                .iter()
+                .zip(&expected)
+                .all(|(a, b)| (a - b).norm() < 1e-9)
+        );
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let fixed: FixedCepstrumExtractor<f32, LEN> = FixedCepstrumExtractor::new();
+        let signal: [f32; LEN] = core::array::from_fn(|i| ((i * 7919) % 127) as f32 / 127.);
+
+        let mut expected = [0.; LEN / 2];
+        fixed.rceps_into(&signal, &mut expected);
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let mut out = [0.; LEN / 2];
+                    fixed.rceps_into(&signal, &mut out);
+                    assert_eq!(out, expected);
+                });
+            }
+        });
+    }
+}
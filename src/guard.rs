@@ -0,0 +1,412 @@
+//! Module containing the guard used to check out an instance of the extractor.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::CepFloat;
+use crate::backend::FftBackend;
+use crate::fft::{CepFft, Plans};
+use crate::num_complex::{Complex, ComplexFloat};
+use crate::num_traits::Zero;
+
+/// Exclusive access to an instance of a [`crate::CepstrumExtractor`], obtained through
+/// [`crate::CepstrumExtractor::acquire`] or [`crate::CepstrumExtractor::try_acquire`].
+///
+/// While the guard is alive, no other thread can use its instance; the instance is given back to
+/// the extractor when the guard is dropped. The guard keeps using the length the extractor had
+/// when the guard was created, even if it is changed in the meantime with
+/// [`crate::CepstrumExtractor::set_len`].
+///
+/// ## Examples
+/// ```rust
+/// use std::sync::Arc;
+/// use std::thread;
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::num_complex::Complex;
+/// use cepstrum_extractor::num_traits::Zero;
+///
+/// const CEP_LEN: usize = 10;
+///
+/// let extractor: Arc<CepstrumExtractor<f32>> = Arc::new(CepstrumExtractor::new(CEP_LEN));
+/// let signal: Vec<Complex<f32>> = vec![Complex::zero(); 100];
+///
+/// thread::scope(|s| {
+///     for thread_chunk in signal.chunks(50) {
+///         let ex = extractor.clone();
+///         s.spawn(move || {
+///             // New instances are added as needed.
+///             let mut instance = ex.acquire();
+///             for chunk in thread_chunk.chunks(CEP_LEN) {
+///                 let real_ceps = instance.rceps_to_vec(chunk);
+///             }
+///         });
+///     }
+/// });
+/// ```
+/// Where the scratch of a guard is taken from, and given back to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Source {
+    /// Instance used by index, by the `*_with_instance_*` methods.
+    Indexed(usize),
+    /// Instance of the pool, checked out without an index.
+    Pooled(usize),
+    /// Scratch of the calling thread.
+    Local,
+}
+
+pub struct InstanceGuard<'a, T: CepFloat> {
+    fft: &'a CepFft<T>,
+    source: Source,
+    /// Plans of the extractor when the guard was created.
+    plans: Arc<Plans<T>>,
+    /// Buffer of the out-of-place computations, followed by the scratch of the FFTs.
+    scratch: Vec<Complex<T>>,
+}
+
+impl<'a, T: CepFloat> InstanceGuard<'a, T> {
+    pub(crate) fn new(fft: &'a CepFft<T>, source: Source, mut scratch: Vec<Complex<T>>) -> Self {
+        let plans = fft.plans();
+        scratch.resize(plans.work_len() + plans.scratch_len(), Complex::zero());
+
+        Self {
+            fft,
+            source,
+            plans,
+            scratch,
+        }
+    }
+
+    /// Index of the instance held by this guard, or `None` if the guard holds the scratch of the
+    /// calling thread (see [`crate::CepstrumExtractor::with_thread_local_scratches`]).
+    ///
+    /// Instances checked out by [`crate::CepstrumExtractor::acquire`] are kept apart from the ones
+    /// used by index, so their index is not the one of the `*_with_instance_*` methods.
+    #[inline(always)]
+    pub fn index(&self) -> Option<usize> {
+        match self.source {
+            Source::Indexed(index) | Source::Pooled(index) => Some(index),
+            Source::Local => None,
+        }
+    }
+
+    /// Length of the window this guard computes with, i.e. the one of the extractor when the
+    /// guard was created.
+    #[inline(always)]
+    pub fn win_len(&self) -> usize {
+        self.plans.len()
+    }
+
+    /// Length of the scratch of the FFTs.
+    #[cfg(test)]
+    pub(crate) fn scratch_len(&self) -> usize {
+        self.scratch.len() - self.plans.work_len()
+    }
+
+    /// Forward FFT of `signal`, in place.
+    #[inline]
+    pub(crate) fn fft_mut(&mut self, signal: &mut [Complex<T>]) {
+        self.plans
+            .get(signal.len())
+            .forward(signal, &mut self.scratch[self.plans.work_len()..]);
+    }
+
+    /// Inverse (unnormalised) FFT of `signal`, in place.
+    #[inline]
+    pub(crate) fn ifft_mut(&mut self, signal: &mut [Complex<T>]) {
+        self.plans
+            .get(signal.len())
+            .inverse(signal, &mut self.scratch[self.plans.work_len()..]);
+    }
+
+    /// Scratch of the FFTs, grown to at least `len` if needed.
+    fn fft_scratch(&mut self, len: usize) -> &mut [Complex<T>] {
+        let work_len = self.plans.work_len();
+        if self.scratch.len() < work_len + len {
+            self.scratch.resize(work_len + len, Complex::zero());
+        }
+
+        &mut self.scratch[work_len..]
+    }
+
+    /// Forward FFT of `signal`, in place, computed by `plan` instead of the plans of the
+    /// extractor.
+    #[inline]
+    pub(crate) fn fft_with_plan_mut(
+        &mut self,
+        plan: &dyn FftBackend<T>,
+        signal: &mut [Complex<T>],
+    ) {
+        plan.forward(signal, self.fft_scratch(plan.scratch_len()));
+    }
+
+    /// Inverse (unnormalised) FFT of `signal`, in place, computed by `plan` instead of the plans
+    /// of the extractor.
+    #[inline]
+    pub(crate) fn ifft_with_plan_mut(
+        &mut self,
+        plan: &dyn FftBackend<T>,
+        signal: &mut [Complex<T>],
+    ) {
+        plan.inverse(signal, self.fft_scratch(plan.scratch_len()));
+    }
+
+    /// Power spectrum `|X(k)|^2` of `signal` for `k` in `0..=signal.len() / 2`.
+    pub(crate) fn power_spectrum(&mut self, signal: &[Complex<T>]) -> Vec<T> {
+        let mut copied = signal.to_vec();
+
+        self.fft_mut(&mut copied);
+        copied.truncate(copied.len() / 2 + 1);
+
+        copied.iter().map(|c| c.norm_sqr()).collect()
+    }
+
+    fn _ceps_mut(&mut self, signal: &mut [Complex<T>], f: fn(&mut [Complex<T>])) {
+        self.fft_mut(signal);
+        f(signal);
+        self.ifft_mut(signal);
+    }
+
+    /// Computes the cepstrum of `signal` in the work buffer, then copies its meaningful half into
+    /// `out`.
+    fn _ceps_into<S: Copy + Into<Complex<T>>>(
+        &mut self,
+        signal: &[S],
+        out: &mut [T],
+        f: fn(&mut [Complex<T>]),
+    ) {
+        let len = signal.len();
+        assert!(self.plans.serves(len), "No plan for signals long {}", len);
+        assert_eq!(out.len(), len / 2, "Output long {} instead of {}", out.len(), len / 2);
+
+        let (work, scratch) = self.scratch.split_at_mut(self.plans.work_len());
+        let (work, plan) = (&mut work[..len], self.plans.get(len));
+
+        work.iter_mut()
+            .zip(signal)
+            .for_each(|(w, s)| *w = (*s).into());
+
+        plan.forward(work, scratch);
+        f(work);
+        plan.inverse(work, scratch);
+
+        out.iter_mut().zip(work.iter()).for_each(|(o, w)| *o = w.re);
+    }
+
+    // ----------------------------------------- REAL ----------------------------------------------
+
+    /// As [`crate::CepstrumExtractor::rceps_mut`], but uses the instance held by this guard.
+    pub fn rceps_mut(&mut self, signal: &mut [Complex<T>]) {
+        self._ceps_mut(signal, T::log_magnitude)
+    }
+
+    /// As [`crate::CepstrumExtractor::rceps_to_vec`], but uses the instance held by this guard.
+    pub fn rceps_to_vec(&mut self, signal: &[Complex<T>]) -> Vec<Complex<T>> {
+        let mut copied = signal.to_vec();
+
+        self.rceps_mut(&mut copied);
+        copied.truncate(copied.len() / 2);
+
+        copied
+    }
+
+    /// As [`crate::CepstrumExtractor::rceps_into`], but uses the instance held by this guard.
+    pub fn rceps_into<S: Copy + Into<Complex<T>>>(&mut self, signal: &[S], out: &mut [T]) {
+        self._ceps_into(signal, out, T::log_magnitude)
+    }
+
+    // --------------------------------------- COMPLEX ---------------------------------------------
+
+    /// As [`crate::CepstrumExtractor::cceps_mut`], but uses the instance held by this guard.
+    pub fn cceps_mut(&mut self, signal: &mut [Complex<T>]) {
+        self._ceps_mut(signal, T::complex_log)
+    }
+
+    /// As [`crate::CepstrumExtractor::cceps_to_vec`], but uses the instance held by this guard.
+    pub fn cceps_to_vec(&mut self, signal: &[Complex<T>]) -> Vec<Complex<T>> {
+        let mut copied = signal.to_vec();
+
+        self.cceps_mut(&mut copied);
+        copied.truncate(copied.len() / 2);
+
+        copied
+    }
+
+    /// As [`crate::CepstrumExtractor::cceps_into`], but uses the instance held by this guard.
+    pub fn cceps_into<S: Copy + Into<Complex<T>>>(&mut self, signal: &[S], out: &mut [T]) {
+        self._ceps_into(signal, out, T::complex_log)
+    }
+
+    // ---------------------------------------- CROSS ----------------------------------------------
+
+    fn _xceps_mut(
+        &mut self,
+        signal: &mut [Complex<T>],
+        other: &mut [Complex<T>],
+        f: fn(&mut [Complex<T>]),
+    ) {
+        assert_eq!(signal.len(), other.len(), "Signals of different length");
+
+        self.fft_mut(signal);
+        self.fft_mut(other);
+
+        signal.iter_mut().zip(other.iter()).for_each(|(x, y)| {
+            *x = *x * y.conj();
+        });
+        f(signal);
+
+        self.ifft_mut(signal);
+    }
+
+    /// Complex logarithm of the cross-spectrum, with the phase unwrapped from DC towards both
+    /// ends of the spectrum, so that it stays odd for real signals.
+    fn cx_f(cross_spectrum: &mut [Complex<T>]) {
+        let len = cross_spectrum.len();
+        let mut phases: Vec<T> = cross_spectrum.iter().map(|x| x.arg()).collect();
+
+        let unwrap = |prev: T, cur: T| {
+            let tau = T::PI() + T::PI();
+            cur - ((cur - prev) / tau).round() * tau
+        };
+        for k in 1..=len / 2 {
+            phases[k] = unwrap(phases[k - 1], phases[k]);
+        }
+        for k in (len / 2 + 1..len).rev() {
+            phases[k] = unwrap(phases[(k + 1) % len], phases[k]);
+        }
+
+        cross_spectrum
+            .iter_mut()
+            .zip(phases)
+            .for_each(|(x, phase)| {
+                *x = Complex::new(x.abs().max(T::min_positive_value()).ln(), phase);
+            });
+    }
+
+    /// As [`crate::CepstrumExtractor::rxceps_mut`], but uses the instance held by this guard.
+    pub fn rxceps_mut(&mut self, signal: &mut [Complex<T>], other: &mut [Complex<T>]) {
+        self._xceps_mut(signal, other, T::log_magnitude)
+    }
+
+    /// As [`crate::CepstrumExtractor::rxceps_to_vec`], but uses the instance held by this guard.
+    pub fn rxceps_to_vec(
+        &mut self,
+        signal: &[Complex<T>],
+        other: &[Complex<T>],
+    ) -> Vec<Complex<T>> {
+        let mut copied = signal.to_vec();
+        let mut other = other.to_vec();
+
+        self.rxceps_mut(&mut copied, &mut other);
+        copied.truncate(copied.len() / 2);
+
+        copied
+    }
+
+    /// As [`crate::CepstrumExtractor::cxceps_mut`], but uses the instance held by this guard.
+    pub fn cxceps_mut(&mut self, signal: &mut [Complex<T>], other: &mut [Complex<T>]) {
+        self._xceps_mut(signal, other, Self::cx_f)
+    }
+
+    /// As [`crate::CepstrumExtractor::cxceps_to_vec`], but uses the instance held by this guard.
+    pub fn cxceps_to_vec(
+        &mut self,
+        signal: &[Complex<T>],
+        other: &[Complex<T>],
+    ) -> Vec<Complex<T>> {
+        let mut copied = signal.to_vec();
+        let mut other = other.to_vec();
+
+        self.cxceps_mut(&mut copied, &mut other);
+        copied.truncate(copied.len() / 2);
+
+        copied
+    }
+}
+
+impl<T: CepFloat> Drop for InstanceGuard<'_, T> {
+    fn drop(&mut self) {
+        let scratch = mem::take(&mut self.scratch);
+
+        match self.source {
+            // SAFETY: the guard holds the instance it checked out.
+            Source::Indexed(index) => unsafe { self.fft.checkin(index, false, scratch) },
+            Source::Pooled(index) => unsafe { self.fft.checkin(index, true, scratch) },
+            Source::Local => self.fft.checkin_local(scratch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::CepstrumExtractor;
+    use crate::num_complex::Complex;
+
+    #[test]
+    fn exclusive_instances() {
+        let extractor: CepstrumExtractor<f32> = CepstrumExtractor::new(16);
+        extractor.extend_instances(2);
+
+        let a = extractor.acquire();
+        let b = extractor.try_acquire().unwrap();
+        assert_ne!(a.index(), b.index());
+        assert!(extractor.try_acquire().is_none());
+
+        // Exhausted: `acquire` adds a new instance.
+        let c = extractor.acquire();
+        assert_eq!(c.index(), Some(2));
+
+        drop(b);
+        assert_eq!(extractor.try_acquire().unwrap().index(), Some(1));
+    }
+
+    #[test]
+    fn plain_calls_while_in_use() {
+        let extractor: CepstrumExtractor<f32> = CepstrumExtractor::new(16);
+        let signal = [Complex::from(1f32); 16];
+
+        // The methods without an index take another instance.
+        let mut guard = extractor.acquire();
+        assert_eq!(guard.index(), Some(0));
+        assert_eq!(extractor.rceps_to_vec(&signal), guard.rceps_to_vec(&signal));
+        drop(guard);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..100 {
+                        extractor.cceps_to_vec(&signal);
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn indexed_and_plain_calls() {
+        const THREADS: usize = 4;
+        let extractor: CepstrumExtractor<f32> = CepstrumExtractor::new(16);
+        extractor.extend_instances(THREADS);
+        let signal = [Complex::from(1f32); 16];
+
+        // The methods without an index never take the instances used by index.
+        thread::scope(|s| {
+            for i in 0..THREADS {
+                let (extractor, signal) = (&extractor, &signal);
+                s.spawn(move || {
+                    for _ in 0..100 {
+                        extractor.rceps_with_instance_to_vec(signal, i);
+                    }
+                });
+            }
+            s.spawn(|| {
+                for _ in 0..100 {
+                    extractor.rceps_to_vec(&signal);
+                    extractor.acquire().cceps_to_vec(&signal);
+                }
+            });
+        });
+    }
+}
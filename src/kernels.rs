@@ -0,0 +1,433 @@
+//! Module containing the kernels mapping a spectrum to its logarithm.
+//!
+//! Each kernel has a scalar version, built upon the logarithm of the standard library, and a
+//! vectorized one, built upon polynomial approximations of `ln` and `atan2` which the compiler
+//! turns into SIMD instructions. The latter is chosen at runtime when the CPU supports AVX2 and
+//! FMA (x86_64) or NEON (aarch64).
+//!
+//! More information at: <https://en.wikipedia.org/wiki/Natural_logarithm#Series>
+
+use crate::num_complex::Complex;
+
+/// Logarithmic mappings of a spectrum, implemented for `f32` and `f64`.
+pub trait Kernels: Sized {
+    /// Maps every value `x` to `ln|x|`, or to `|x|` if the real part of `x` is zero.
+    fn log_magnitude(spectrum: &mut [Complex<Self>]);
+
+    /// Writes `ln|x|^2` of every value `x` into `out`, floored at the logarithm of the smallest
+    /// positive value.
+    fn log_power(spectrum: &[Complex<Self>], out: &mut [Self]);
+
+    /// Maps every value `x` whose real part is not zero to `ln x`, with the phase in `(-π, π]`.
+    fn complex_log(spectrum: &mut [Complex<Self>]);
+}
+
+/// Instruction sets the vectorized kernels are compiled for.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Isa {
+    Scalar,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(target_arch = "aarch64")]
+    Neon,
+}
+
+/// Best instruction set supported by the running CPU; detection is cached by the standard library.
+/// Without it, the instruction sets enabled at compile time are used.
+#[inline]
+fn isa() -> Isa {
+    #[cfg(all(target_arch = "x86_64", feature = "std"))]
+    if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+        return Isa::Avx2;
+    }
+
+    #[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+    if cfg!(all(target_feature = "avx2", target_feature = "fma")) {
+        return Isa::Avx2;
+    }
+
+    #[cfg(all(target_arch = "aarch64", feature = "std"))]
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        return Isa::Neon;
+    }
+
+    #[cfg(all(target_arch = "aarch64", not(feature = "std")))]
+    if cfg!(target_feature = "neon") {
+        return Isa::Neon;
+    }
+
+    Isa::Scalar
+}
+
+/// Implements [`Kernels`] for a float type, with the kernels in module `$module`.
+///
+/// `$log_terms` and `$atan_terms` are the number of terms of the series of `ln` and `atan` needed
+/// to reach the precision of the type, and `2^$sub_exp` scales subnormal values into normal ones.
+macro_rules! impl_kernels {
+    ($float:ident, $module:ident, $log_terms:expr, $atan_terms:expr, $sub_exp:expr) => {
+        // On other architectures, only the scalar kernels are used.
+        #[cfg_attr(
+            not(any(target_arch = "x86_64", target_arch = "aarch64")),
+            allow(dead_code)
+        )]
+        mod $module {
+            use core::$float::consts::{FRAC_PI_2, FRAC_PI_6, LN_2, PI, SQRT_2};
+
+            use crate::num_complex::{Complex, ComplexFloat};
+
+            const MANTISSA_BITS: u32 = $float::MANTISSA_DIGITS - 1;
+            const BIAS: $float = ($float::MAX_EXP - 1) as $float;
+            /// Adding its bits to an integer below `2^MANTISSA_BITS` gives the integer plus this.
+            const MAGIC: $float = (1u64 << MANTISSA_BITS) as $float;
+
+            const TAN_PI_12: $float = (2. - 1.732_050_807_568_877_2_f64) as $float;
+            const FRAC_1_SQRT_3: $float = 0.577_350_269_189_625_8_f64 as $float;
+
+            /// `1 / (2k + 1)`, i.e. the coefficients of `atanh(s) / s` in `s^2`.
+            const LOG_COEFFS: [$float; $log_terms] = {
+                let mut c = [0.; $log_terms];
+                let mut k = 0;
+                while k < $log_terms {
+                    c[k] = 1. / (2 * k + 1) as $float;
+                    k += 1;
+                }
+                c
+            };
+
+            /// `(-1)^k / (2k + 1)`, i.e. the coefficients of `atan(u) / u` in `u^2`.
+            const ATAN_COEFFS: [$float; $atan_terms] = {
+                let mut c = [0.; $atan_terms];
+                let mut k = 0;
+                while k < $atan_terms {
+                    c[k] = if k % 2 == 0 { 1. } else { -1. } / (2 * k + 1) as $float;
+                    k += 1;
+                }
+                c
+            };
+
+            #[inline(always)]
+            fn horner(x: $float, coeffs: &[$float]) -> $float {
+                coeffs.iter().rev().fold(0., |acc, c| acc * x + c)
+            }
+
+            /// Branch-free `ln(v)`, for `v >= 0`: `v = 2^e * m`, with `m` in `[√2/2, √2)`, and
+            /// `ln(m) = 2 atanh((m - 1) / (m + 1))`.
+            #[inline(always)]
+            pub(super) fn ln(v: $float) -> $float {
+                let subnormal = v < $float::MIN_POSITIVE;
+                let scaled = if subnormal {
+                    v * (2. as $float).powi($sub_exp)
+                } else {
+                    v
+                };
+
+                let bits = scaled.to_bits();
+                let exp_bits = bits >> MANTISSA_BITS;
+                let mut e = $float::from_bits(exp_bits | MAGIC.to_bits()) - MAGIC - BIAS;
+                e -= if subnormal { $sub_exp as $float } else { 0. };
+
+                let mut m = $float::from_bits(
+                    (bits & ((1 << MANTISSA_BITS) - 1)) | (1. as $float).to_bits(),
+                );
+                let high = m > SQRT_2;
+                m = if high { m * 0.5 } else { m };
+                e += if high { 1. } else { 0. };
+
+                let s = (m - 1.) / (m + 1.);
+                let ln = e * LN_2 + 2. * s * horner(s * s, &LOG_COEFFS);
+
+                if v == 0. {
+                    $float::NEG_INFINITY
+                } else if v < $float::INFINITY {
+                    ln
+                } else {
+                    v
+                }
+            }
+
+            /// Branch-free `atan2(y, x)`: the ratio of the smallest to the largest absolute value
+            /// is reduced below `tan(π/12)` through `atan(t) = π/6 + atan((t - 1/√3) / (1 + t/√3))`.
+            #[inline(always)]
+            pub(super) fn atan2(y: $float, x: $float) -> $float {
+                let (ax, ay) = (x.abs(), y.abs());
+                let (max, min) = (ax.max(ay), ax.min(ay));
+                let t = if max == 0. { 0. } else { min / max };
+
+                let reduced = t > TAN_PI_12;
+                let u = if reduced {
+                    (t - FRAC_1_SQRT_3) / (1. + t * FRAC_1_SQRT_3)
+                } else {
+                    t
+                };
+                let mut a = if reduced { FRAC_PI_6 } else { 0. } + u * horner(u * u, &ATAN_COEFFS);
+
+                a = if ay > ax { FRAC_PI_2 - a } else { a };
+                a = if x.is_sign_negative() { PI - a } else { a };
+                a.copysign(y)
+            }
+
+            /// `ln|x|` and `|x|`, without overflowing nor underflowing.
+            #[inline(always)]
+            fn ln_abs(x: Complex<$float>) -> ($float, $float) {
+                let (ar, ai) = (x.re.abs(), x.im.abs());
+                let (max, min) = (ar.max(ai), ar.min(ai));
+                let r = if max == 0. { 0. } else { min / max };
+                let q = 1. + r * r;
+
+                (ln(max) + 0.5 * ln(q), max * q.sqrt())
+            }
+
+            #[inline(always)]
+            pub(super) fn log_magnitude(spectrum: &mut [Complex<$float>]) {
+                spectrum.iter_mut().for_each(|x| {
+                    let (ln, abs) = ln_abs(*x);
+                    *x = Complex::from(if x.re == 0. { abs } else { ln });
+                });
+            }
+
+            #[inline(always)]
+            pub(super) fn log_power(spectrum: &[Complex<$float>], out: &mut [$float]) {
+                let floor = $float::MIN_POSITIVE.ln();
+
+                out.iter_mut().zip(spectrum).for_each(|(o, x)| {
+                    *o = (2. * ln_abs(*x).0).max(floor);
+                });
+            }
+
+            #[inline(always)]
+            pub(super) fn complex_log(spectrum: &mut [Complex<$float>]) {
+                spectrum.iter_mut().for_each(|x| {
+                    let ln = Complex::new(ln_abs(*x).0, atan2(x.im, x.re));
+                    *x = if x.re == 0. { *x } else { ln };
+                });
+            }
+
+            pub(super) fn log_magnitude_scalar(spectrum: &mut [Complex<$float>]) {
+                spectrum.iter_mut().for_each(|x| {
+                    *x = Complex::from(if x.re == 0. { x.abs() } else { x.abs().ln() });
+                });
+            }
+
+            pub(super) fn log_power_scalar(spectrum: &[Complex<$float>], out: &mut [$float]) {
+                let floor = $float::MIN_POSITIVE.ln();
+
+                // `2 ln|x|` rather than `ln|x|^2`, which overflows for large values.
+                out.iter_mut().zip(spectrum).for_each(|(o, x)| {
+                    *o = (2. * x.abs().ln()).max(floor);
+                });
+            }
+
+            pub(super) fn complex_log_scalar(spectrum: &mut [Complex<$float>]) {
+                spectrum.iter_mut().for_each(|x| {
+                    if x.re != 0. {
+                        *x = x.ln();
+                    }
+                });
+            }
+
+            #[cfg(target_arch = "x86_64")]
+            pub(super) mod avx2 {
+                use crate::num_complex::Complex;
+
+                #[target_feature(enable = "avx2,fma")]
+                pub(in super::super) fn log_magnitude(spectrum: &mut [Complex<$float>]) {
+                    super::log_magnitude(spectrum)
+                }
+
+                #[target_feature(enable = "avx2,fma")]
+                pub(in super::super) fn log_power(
+                    spectrum: &[Complex<$float>],
+                    out: &mut [$float],
+                ) {
+                    super::log_power(spectrum, out)
+                }
+
+                #[target_feature(enable = "avx2,fma")]
+                pub(in super::super) fn complex_log(spectrum: &mut [Complex<$float>]) {
+                    super::complex_log(spectrum)
+                }
+            }
+
+            #[cfg(target_arch = "aarch64")]
+            pub(super) mod neon {
+                use crate::num_complex::Complex;
+
+                #[target_feature(enable = "neon")]
+                pub(in super::super) fn log_magnitude(spectrum: &mut [Complex<$float>]) {
+                    super::log_magnitude(spectrum)
+                }
+
+                #[target_feature(enable = "neon")]
+                pub(in super::super) fn log_power(
+                    spectrum: &[Complex<$float>],
+                    out: &mut [$float],
+                ) {
+                    super::log_power(spectrum, out)
+                }
+
+                #[target_feature(enable = "neon")]
+                pub(in super::super) fn complex_log(spectrum: &mut [Complex<$float>]) {
+                    super::complex_log(spectrum)
+                }
+            }
+        }
+
+        impl Kernels for $float {
+            fn log_magnitude(spectrum: &mut [Complex<$float>]) {
+                match isa() {
+                    Isa::Scalar => $module::log_magnitude_scalar(spectrum),
+                    // SAFETY: the CPU supports the features the kernel is compiled for.
+                    #[cfg(target_arch = "x86_64")]
+                    Isa::Avx2 => unsafe { $module::avx2::log_magnitude(spectrum) },
+                    #[cfg(target_arch = "aarch64")]
+                    Isa::Neon => unsafe { $module::neon::log_magnitude(spectrum) },
+                }
+            }
+
+            fn log_power(spectrum: &[Complex<$float>], out: &mut [$float]) {
+                match isa() {
+                    Isa::Scalar => $module::log_power_scalar(spectrum, out),
+                    #[cfg(target_arch = "x86_64")]
+                    Isa::Avx2 => unsafe { $module::avx2::log_power(spectrum, out) },
+                    #[cfg(target_arch = "aarch64")]
+                    Isa::Neon => unsafe { $module::neon::log_power(spectrum, out) },
+                }
+            }
+
+            fn complex_log(spectrum: &mut [Complex<$float>]) {
+                match isa() {
+                    Isa::Scalar => $module::complex_log_scalar(spectrum),
+                    #[cfg(target_arch = "x86_64")]
+                    Isa::Avx2 => unsafe { $module::avx2::complex_log(spectrum) },
+                    #[cfg(target_arch = "aarch64")]
+                    Isa::Neon => unsafe { $module::neon::complex_log(spectrum) },
+                }
+            }
+        }
+    };
+}
+
+impl_kernels!(f32, f32_kernels, 6, 7, 32);
+impl_kernels!(f64, f64_kernels, 11, 15, 64);
+
+/// Entry points of the benchmarks: the `f32` kernels, as dispatched and in their scalar version.
+#[doc(hidden)]
+pub mod bench {
+    use super::{Kernels, f32_kernels};
+    use crate::num_complex::Complex;
+
+    pub fn log_magnitude(spectrum: &mut [Complex<f32>]) {
+        f32::log_magnitude(spectrum)
+    }
+
+    pub fn log_magnitude_scalar(spectrum: &mut [Complex<f32>]) {
+        f32_kernels::log_magnitude_scalar(spectrum)
+    }
+
+    pub fn complex_log(spectrum: &mut [Complex<f32>]) {
+        f32::complex_log(spectrum)
+    }
+
+    pub fn complex_log_scalar(spectrum: &mut [Complex<f32>]) {
+        f32_kernels::complex_log_scalar(spectrum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{f32_kernels, f64_kernels};
+    use crate::num_complex::Complex;
+
+    /// Values of every magnitude, sign and quadrant, including zeros and subnormals.
+    fn values<T: Copy>(from: impl Fn(f64) -> T) -> Vec<Complex<T>> {
+        let parts = [
+            0., -0., 1., -1., 0.5, 3., -7.25, 1e-3, -2e4, 1e-30, 1e30, 1e-40, 123.456, -0.1,
+        ];
+        parts
+            .iter()
+            .flat_map(|re| parts.iter().map(move |im| (*re, *im)))
+            .map(|(re, im)| Complex::new(from(re), from(im)))
+            .collect()
+    }
+
+    fn assert_close<T: Into<f64> + Copy>(a: &[Complex<T>], b: &[Complex<T>], tolerance: f64) {
+        let close = |a: f64, b: f64| {
+            if a.is_finite() && b.is_finite() {
+                (a - b).abs() <= tolerance * b.abs().max(1.)
+            } else {
+                a == b || (a.is_nan() && b.is_nan())
+            }
+        };
+
+        for (a, b) in a.iter().zip(b) {
+            let (ar, ai, br, bi) = (a.re.into(), a.im.into(), b.re.into(), b.im.into());
+            assert!(
+                close(ar, br) && close(ai, bi),
+                "({ar}, {ai}) != ({br}, {bi})"
+            );
+        }
+    }
+
+    /// Checks the kernels of `$kernels` against the scalar ones of `$module`.
+    macro_rules! check {
+        ($float:ident, $module:ident, $kernels:path, $tolerance:expr) => {{
+            use $kernels as kernels;
+
+            let input = values(|v| v as $float);
+
+            let (mut vector, mut scalar) = (input.clone(), input.clone());
+            kernels::log_magnitude(&mut vector);
+            $module::log_magnitude_scalar(&mut scalar);
+            assert_close(&vector, &scalar, $tolerance);
+
+            let (mut vector, mut scalar) = (input.clone(), input.clone());
+            kernels::complex_log(&mut vector);
+            $module::complex_log_scalar(&mut scalar);
+            assert_close(&vector, &scalar, $tolerance);
+
+            let (mut vector, mut scalar) = (vec![0.; input.len()], vec![0.; input.len()]);
+            kernels::log_power(&input, &mut vector);
+            $module::log_power_scalar(&input, &mut scalar);
+            let (vector, scalar): (Vec<_>, Vec<_>) = (
+                vector.into_iter().map(Complex::from).collect(),
+                scalar.into_iter().map(Complex::from).collect(),
+            );
+            assert_close(&vector, &scalar, $tolerance);
+        }};
+    }
+
+    #[test]
+    fn vectorized_match_scalar() {
+        // The portable code of the vectorized kernels, on any CPU.
+        check!(f32, f32_kernels, f32_kernels, 1e-6);
+        check!(f64, f64_kernels, f64_kernels, 1e-14);
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn avx2_match_scalar() {
+        if !(is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")) {
+            return;
+        }
+
+        // SAFETY: the CPU supports the features the kernels are compiled for.
+        unsafe {
+            check!(f32, f32_kernels, f32_kernels::avx2, 1e-6);
+            check!(f64, f64_kernels, f64_kernels::avx2, 1e-14);
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "aarch64")]
+    fn neon_match_scalar() {
+        if !std::arch::is_aarch64_feature_detected!("neon") {
+            return;
+        }
+
+        // SAFETY: the CPU supports the features the kernels are compiled for.
+        unsafe {
+            check!(f32, f32_kernels, f32_kernels::neon, 1e-6);
+            check!(f64, f64_kernels, f64_kernels::neon, 1e-14);
+        }
+    }
+}
@@ -1,21 +1,86 @@
 #![doc = include_str!("../README.md")]
+// Tests link the standard library anyway, and use its prelude.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use core::fmt::Debug;
+
+pub use num_complex;
+pub use num_traits;
+use num_complex::Complex;
+#[cfg(not(feature = "std"))]
+use num_traits::{FromPrimitive, Signed};
+use num_traits::{Float, FloatConst};
+#[cfg(feature = "std")]
 use rustfft::FftNum;
-pub use rustfft::num_complex;
-pub use rustfft::num_traits;
-use rustfft::num_traits::{Float, FloatConst};
+
+use kernels::Kernels;
 
 pub use cepstrum::CepstrumExtractor;
-pub use conversions::{ComplexToReal, RealToComplex};
+pub use fixed::FixedCepstrumExtractor;
+pub use guard::InstanceGuard;
+pub use plan::PlanCache;
+pub use conversions::{ComplexToReal, PcmFormat, PcmSample, PcmToReal, RealToComplex};
+#[doc(hidden)]
+pub use kernels::bench as __kernels;
 
 mod fft;
+pub mod backend;
 mod cepstrum;
+mod fixed;
+mod guard;
+mod kernels;
+#[cfg(feature = "rayon")]
+mod par;
+mod plan;
+mod slots;
 pub mod windows;
 mod conversions;
+mod dsp;
+pub mod band;
+pub mod features;
+pub mod distance;
+pub mod dtw;
+pub mod vibration;
+pub mod voice;
+pub mod zoom;
 
 /// Trait implemented for types that can be used with the cepstrum extractor. Currently, it supports
 /// `f32` and `f64`.
-pub trait CepFloat: FftNum + Float + FloatConst {}
+#[cfg(feature = "std")]
+pub trait CepFloat: FftNum + Float + FloatConst + Kernels {}
+
+/// Trait implemented for types that can be used with the cepstrum extractor. Currently, it supports
+/// `f32` and `f64`.
+#[cfg(not(feature = "std"))]
+pub trait CepFloat:
+    Copy + FromPrimitive + Signed + Sync + Send + Debug + 'static + Float + FloatConst + Kernels
+{
+}
 
 impl CepFloat for f32 {}
 impl CepFloat for f64 {}
+
+/// Trait implemented for the values a cepstral coefficient can be read from: plain reals, as
+/// returned by the modules in [`features`], or `Complex`, as returned by [`CepstrumExtractor`], of
+/// which only the real part is considered.
+pub trait Coefficient<T: CepFloat>: Copy {
+    /// Returns the value of the coefficient.
+    fn value(&self) -> T;
+}
+
+impl<T: CepFloat> Coefficient<T> for T {
+    #[inline(always)]
+    fn value(&self) -> T {
+        *self
+    }
+}
+
+impl<T: CepFloat> Coefficient<T> for Complex<T> {
+    #[inline(always)]
+    fn value(&self) -> T {
+        self.re
+    }
+}
@@ -0,0 +1,125 @@
+//! Module containing the parallel methods of the extractor, enabled by the `rayon` feature.
+
+use rayon::prelude::*;
+
+use crate::num_complex::Complex;
+use crate::{CepFloat, CepstrumExtractor, InstanceGuard};
+
+impl<T: CepFloat> CepstrumExtractor<T> {
+    /// Number of frames long as the window of the extractor, each `hop` samples after the
+    /// previous one, fitting into a signal long `signal_len`.
+    pub fn frame_count(&self, signal_len: usize, hop: usize) -> usize {
+        assert!(hop > 0, "Hop must be greater than zero");
+
+        match signal_len.checked_sub(self.win_len()) {
+            Some(rest) => rest / hop + 1,
+            None => 0,
+        }
+    }
+
+    /// Splits `signal` into frames long as the window of the extractor, each `hop` samples after
+    /// the previous one, and extracts their real cepstrums across the Rayon pool.
+    ///
+    /// The meaningful half of the cepstrum of frame `i` is written into row `i` of `out`, a
+    /// row-major matrix of [`Self::frame_count`] rows and `win_len / 2` columns. Frames are
+    /// transformed as they are, so they should be already windowed. Instances are acquired (and
+    /// added, if needed) internally, one per Rayon job.
+    ///
+    /// Panics if `out` does not have the size of the matrix.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use cepstrum_extractor::CepstrumExtractor;
+    ///
+    /// const WIN_LEN: usize = 128;
+    ///
+    /// let extractor: CepstrumExtractor<f32> = CepstrumExtractor::new(WIN_LEN);
+    /// let signal: Vec<f32> = (0..WIN_LEN * 64).map(|i| (i as f32 * 0.1).sin()).collect();
+    ///
+    /// let frames = extractor.frame_count(signal.len(), WIN_LEN / 2);
+    /// let mut cepstrums = vec![0f32; frames * WIN_LEN / 2];
+    /// extractor.par_rceps_frames(&signal, WIN_LEN / 2, &mut cepstrums);
+    /// ```
+    pub fn par_rceps_frames<S: Copy + Into<Complex<T>> + Sync>(
+        &self,
+        signal: &[S],
+        hop: usize,
+        out: &mut [T],
+    ) {
+        self.par_frames(signal, hop, out, |guard, frame, row| {
+            guard.rceps_into(frame, row)
+        })
+    }
+
+    /// As [`Self::par_rceps_frames`], but extracts the complex cepstrums, of which only the real
+    /// part is kept.
+    pub fn par_cceps_frames<S: Copy + Into<Complex<T>> + Sync>(
+        &self,
+        signal: &[S],
+        hop: usize,
+        out: &mut [T],
+    ) {
+        self.par_frames(signal, hop, out, |guard, frame, row| {
+            guard.cceps_into(frame, row)
+        })
+    }
+
+    fn par_frames<S: Copy + Into<Complex<T>> + Sync>(
+        &self,
+        signal: &[S],
+        hop: usize,
+        out: &mut [T],
+        f: fn(&mut InstanceGuard<'_, T>, &[S], &mut [T]),
+    ) {
+        let win_len = self.win_len();
+        let frames = self.frame_count(signal.len(), hop);
+        assert_eq!(
+            out.len(),
+            frames * (win_len / 2),
+            "Output length differs from frames * win_len / 2"
+        );
+
+        out.par_chunks_exact_mut(win_len / 2)
+            .enumerate()
+            .for_each_init(
+                || self.acquire(),
+                |guard, (i, row)| f(guard, &signal[i * hop..i * hop + win_len], row),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CepstrumExtractor;
+
+    const WIN_LEN: usize = 64;
+
+    #[test]
+    fn par_frames_in_order() {
+        let extractor: CepstrumExtractor<f64> = CepstrumExtractor::new(WIN_LEN);
+        let signal: Vec<f64> = (0..WIN_LEN * 50)
+            .map(|i| (i as f64 * 0.05).sin() + ((i * 7919) % 127) as f64 / 127.)
+            .collect();
+        let hop = WIN_LEN / 4;
+
+        let frames = extractor.frame_count(signal.len(), hop);
+        assert_eq!(frames, 197);
+        assert_eq!(extractor.frame_count(WIN_LEN - 1, hop), 0);
+
+        let mut real = vec![0.; frames * WIN_LEN / 2];
+        let mut complex = vec![0.; frames * WIN_LEN / 2];
+        extractor.par_rceps_frames(&signal, hop, &mut real);
+        extractor.par_cceps_frames(&signal, hop, &mut complex);
+
+        let mut expected = [0.; WIN_LEN / 2];
+        for i in 0..frames {
+            let frame = &signal[i * hop..i * hop + WIN_LEN];
+            let row = i * WIN_LEN / 2..(i + 1) * WIN_LEN / 2;
+
+            extractor.rceps_into(frame, &mut expected);
+            assert_eq!(real[row.clone()], expected);
+            extractor.cceps_into(frame, &mut expected);
+            assert_eq!(complex[row], expected);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Module containing the cache of FFT plans shared between extractors.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::any::{Any, TypeId};
+#[cfg(feature = "std")]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+#[cfg(feature = "std")]
+use rustfft::FftPlanner;
+#[cfg(not(feature = "std"))]
+use spin::{Mutex, MutexGuard};
+
+use crate::CepFloat;
+use crate::backend::FftBackend;
+#[cfg(not(feature = "std"))]
+use crate::backend::{DftBackend, Radix2Backend};
+#[cfg(feature = "std")]
+use crate::backend::RustFftBackend;
+
+/// Thread-safe cache of FFT plans, which can be shared between extractors of any length.
+///
+/// Plans are [`RustFftBackend`]s; they (and their twiddle tables) are computed only once per
+/// length and then shared by all the extractors using the same cache. Without the `std` feature,
+/// plans are `Radix2Backend`s, or `DftBackend`s for lengths which are not a power of two.
+/// [`crate::CepstrumExtractor::new`] uses the global cache of its float type ([`Self::global`]); a
+/// cache of its own can be passed with [`crate::CepstrumExtractor::with_plan_cache`].
+///
+/// Plans are kept until removed with [`Self::remove`] or [`Self::clear`], so a cache serving many
+/// different lengths keeps growing; extractors keep the plans they already use anyway.
+///
+/// ## Examples
+/// ```rust
+/// use std::sync::Arc;
+/// use cepstrum_extractor::{CepstrumExtractor, PlanCache};
+///
+/// let cache: Arc<PlanCache<f32>> = Arc::new(PlanCache::new());
+///
+/// // The second extractor re-uses the plans of the first one.
+/// let a = CepstrumExtractor::with_plan_cache(1024, cache.clone());
+/// let b = CepstrumExtractor::with_plan_cache(1024, cache.clone());
+/// ```
+pub struct PlanCache<T: CepFloat> {
+    #[cfg(feature = "std")]
+    planner: Mutex<FftPlanner<T>>,
+    backends: Mutex<BTreeMap<usize, Arc<dyn FftBackend<T>>>>,
+}
+
+impl<T: CepFloat> PlanCache<T> {
+    /// Builds a new, empty cache.
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            planner: Mutex::new(FftPlanner::new()),
+            backends: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the global cache of `T`, used by default by all the extractors.
+    pub fn global() -> Arc<Self> {
+        static CACHES: Mutex<BTreeMap<TypeId, Box<dyn Any + Send + Sync>>> =
+            Mutex::new(BTreeMap::new());
+
+        lock(&CACHES)
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Arc::new(Self::new())))
+            .downcast_ref::<Arc<Self>>()
+            .unwrap()
+            .clone()
+    }
+
+    /// Plans the FFTs of length `len`, or retrieves them if already planned.
+    pub(crate) fn plan(&self, len: usize) -> Arc<dyn FftBackend<T>> {
+        if let Some(plan) = lock(&self.backends).get(&len) {
+            return plan.clone();
+        }
+
+        // Planned without holding the lock, so that planned lengths can be retrieved meanwhile; if
+        // another thread planned the same length first, its plan is the one kept.
+        let plan = self.new_plan(len);
+        lock(&self.backends).entry(len).or_insert(plan).clone()
+    }
+
+    /// Removes the plan of length `len`, returning whether it was cached.
+    pub fn remove(&self, len: usize) -> bool {
+        lock(&self.backends).remove(&len).is_some()
+    }
+
+    /// Removes all the plans.
+    pub fn clear(&self) {
+        lock(&self.backends).clear();
+    }
+
+    #[cfg(feature = "std")]
+    fn new_plan(&self, len: usize) -> Arc<dyn FftBackend<T>> {
+        Arc::new(RustFftBackend::with_planner(&mut lock(&self.planner), len))
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn new_plan(&self, len: usize) -> Arc<dyn FftBackend<T>> {
+        if len.is_power_of_two() {
+            Arc::new(Radix2Backend::new(len))
+        } else {
+            Arc::new(DftBackend::new(len))
+        }
+    }
+}
+
+/// Locks `mutex`; a poisoned one is used anyway, since plans are never left half-built.
+#[cfg(feature = "std")]
+fn lock<U>(mutex: &Mutex<U>) -> MutexGuard<'_, U> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Locks `mutex`.
+#[cfg(not(feature = "std"))]
+fn lock<U>(mutex: &Mutex<U>) -> MutexGuard<'_, U> {
+    mutex.lock()
+}
+
+impl<T: CepFloat> Default for PlanCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::PlanCache;
+
+    #[test]
+    fn shared_plans() {
+        let a = PlanCache::<f32>::global().plan(256);
+        let b = PlanCache::<f32>::global().plan(256);
+        assert!(Arc::ptr_eq(&a, &b));
+
+        // The global cache is unique per type; other caches have plans of their own.
+        assert!(Arc::ptr_eq(
+            &PlanCache::<f32>::global(),
+            &PlanCache::<f32>::global()
+        ));
+        assert!(!Arc::ptr_eq(&PlanCache::<f32>::new().plan(256), &a));
+        assert_eq!(PlanCache::<f64>::global().plan(256).len(), 256);
+    }
+
+    #[test]
+    fn removed_plans() {
+        let cache = PlanCache::<f32>::new();
+        let a = cache.plan(64);
+
+        assert!(cache.remove(64));
+        assert!(!cache.remove(64));
+        assert!(!Arc::ptr_eq(&cache.plan(64), &a));
+
+        cache.plan(128);
+        cache.clear();
+        assert!(!cache.remove(64) && !cache.remove(128));
+    }
+}
@@ -0,0 +1,211 @@
+//! Module containing the lock-free storage of the scratches of the instances.
+
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+/// Number of buckets: bucket `b` holds `2^b` slots, so they cover any index.
+const BUCKETS: usize = usize::BITS as usize;
+
+/// Value which can be taken by one thread at a time.
+struct Slot<S> {
+    busy: AtomicBool,
+    value: UnsafeCell<S>,
+}
+
+/// Growable sequence of slots, each of which is taken and given back with a single atomic
+/// operation. Slots are never moved nor freed until the sequence is dropped, so growing it does
+/// not disturb the slots in use.
+pub(crate) struct Slots<S> {
+    buckets: [AtomicPtr<Slot<S>>; BUCKETS],
+    len: AtomicUsize,
+    /// The slots are owned.
+    _slots: PhantomData<Slot<S>>,
+}
+
+// SAFETY: the value of a slot is only accessed by the thread which marked it busy.
+unsafe impl<S: Send> Sync for Slots<S> {}
+unsafe impl<S: Send> Send for Slots<S> {}
+
+impl<S> Slots<S> {
+    /// Bucket of the slot `i`, together with the position of the slot in it.
+    #[inline(always)]
+    fn locate(i: usize) -> (usize, usize) {
+        let bucket = (i + 1).ilog2() as usize;
+        (bucket, i + 1 - (1 << bucket))
+    }
+
+    /// # Safety
+    /// `ptr` must have been allocated by [`Self::allocate`] for `bucket`.
+    unsafe fn bucket_from_raw(ptr: *mut Slot<S>, bucket: usize) -> Box<[Slot<S>]> {
+        unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, 1 << bucket)) }
+    }
+}
+
+impl<S: Default> Slots<S> {
+    pub(crate) fn new(len: usize) -> Self {
+        let slots = Self {
+            buckets: [const { AtomicPtr::new(ptr::null_mut()) }; BUCKETS],
+            len: AtomicUsize::new(0),
+            _slots: PhantomData,
+        };
+        slots.extend(len);
+
+        slots
+    }
+
+    #[inline(always)]
+    pub(crate) fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Allocates `bucket`, if not already done.
+    fn allocate(&self, bucket: usize) {
+        if !self.buckets[bucket].load(Ordering::Acquire).is_null() {
+            return;
+        }
+
+        let new = Box::into_raw(
+            (0..1usize << bucket)
+                .map(|_| Slot {
+                    busy: AtomicBool::new(false),
+                    value: UnsafeCell::new(S::default()),
+                })
+                .collect::<Box<[_]>>(),
+        ) as *mut Slot<S>;
+
+        if self.buckets[bucket]
+            .compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            // Allocated by another thread in the meantime.
+            drop(unsafe { Self::bucket_from_raw(new, bucket) });
+        }
+    }
+
+    /// Increases the number of slots to `new_len`.
+    pub(crate) fn extend(&self, new_len: usize) {
+        if new_len == 0 {
+            return;
+        }
+
+        // Buckets are allocated before the slots become visible.
+        (0..=Self::locate(new_len - 1).0).for_each(|b| self.allocate(b));
+        self.len.fetch_max(new_len, Ordering::AcqRel);
+    }
+
+    /// Adds a slot, returning its index.
+    pub(crate) fn push(&self) -> usize {
+        loop {
+            let len = self.len();
+            self.allocate(Self::locate(len).0);
+
+            if self
+                .len
+                .compare_exchange(len, len + 1, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                return len;
+            }
+        }
+    }
+
+    #[inline]
+    fn slot(&self, i: usize) -> Option<&Slot<S>> {
+        if i >= self.len() {
+            return None;
+        }
+
+        let (bucket, pos) = Self::locate(i);
+        // SAFETY: buckets of the slots below `len` are allocated, and never freed before `self`.
+        Some(unsafe { &*self.buckets[bucket].load(Ordering::Acquire).add(pos) })
+    }
+
+    /// Takes the value of slot `i`. Returns `None` if `i` is out of bounds, or `Some(None)` if the
+    /// slot is already taken.
+    #[inline]
+    pub(crate) fn take(&self, i: usize) -> Option<Option<S>> {
+        let slot = self.slot(i)?;
+
+        Some(
+            slot.busy
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .ok()
+                // SAFETY: the slot has just been marked busy by this thread.
+                .map(|_| mem::take(unsafe { &mut *slot.value.get() })),
+        )
+    }
+
+    /// Takes the value of the first slot not taken, together with its index.
+    pub(crate) fn take_any(&self) -> Option<(usize, S)> {
+        (0..self.len()).find_map(|i| self.take(i).flatten().map(|value| (i, value)))
+    }
+
+    /// Gives back the value of slot `i`.
+    ///
+    /// # Safety
+    /// The slot must have been taken by the caller.
+    #[inline]
+    pub(crate) unsafe fn put(&self, i: usize, value: S) {
+        let slot = self.slot(i).unwrap();
+
+        unsafe { *slot.value.get() = value };
+        slot.busy.store(false, Ordering::Release);
+    }
+}
+
+impl<S> Drop for Slots<S> {
+    fn drop(&mut self) {
+        for (bucket, ptr) in self.buckets.iter_mut().enumerate() {
+            let ptr = *ptr.get_mut();
+
+            if !ptr.is_null() {
+                // SAFETY: non-null buckets have been allocated by `allocate`.
+                drop(unsafe { Self::bucket_from_raw(ptr, bucket) });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::Slots;
+
+    #[test]
+    fn exclusive_slots() {
+        let slots: Slots<Vec<usize>> = Slots::new(3);
+        assert_eq!(slots.len(), 3);
+        assert_eq!(slots.take(3), None);
+
+        let (i, _) = slots.take_any().unwrap();
+        assert_eq!(slots.take(i), Some(None));
+        unsafe { slots.put(i, vec![i]) };
+        assert_eq!(slots.take(i), Some(Some(vec![i])));
+        unsafe { slots.put(i, vec![]) };
+
+        // Every thread takes a slot of its own while the sequence grows.
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for _ in 0..100 {
+                        let (i, mut value) = loop {
+                            match slots.take_any() {
+                                Some(taken) => break taken,
+                                None => _ = slots.push(),
+                            }
+                        };
+                        value.push(i);
+                        assert_eq!(value, [i]);
+                        value.clear();
+                        unsafe { slots.put(i, value) };
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,311 @@
+//! Cepstral editing procedure (CEP) module.
+//!
+//! The real cepstrum of a record is edited with a lifter, removing selected rahmonic families
+//! (comb lifter) and/or everything above a quefrency (short-pass lifter); the edited log amplitude
+//! spectrum is then combined with the original phase and transformed back into a time signal.
+//! Removing the families of gears and shafts leaves the signal of the other sources, e.g. faulty
+//! bearings.
+//!
+//! More information at:
+//! - R. B. Randall, N. Sawalhi, "A new method for separating discrete components from a signal",
+//!   Sound and Vibration 2011.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::dsp::idx;
+use crate::num_complex::Complex;
+use crate::vibration::rahmonics::RahmonicFamily;
+use crate::{CepFloat, CepstrumExtractor, InstanceGuard};
+
+/// A notch of the comb lifter: every multiple of `period` (in bins) is removed, with `width`
+/// bins on each side.
+#[derive(Clone, Debug, PartialEq)]
+struct Comb<T: CepFloat> {
+    period: T,
+    width: usize,
+}
+
+/// Cepstral editor.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::num_complex::Complex;
+/// use cepstrum_extractor::vibration::editing::CepstralEditor;
+/// use cepstrum_extractor::vibration::rahmonics::RahmonicDetector;
+///
+/// const LEN: usize = 4096;
+/// const SAMPLE_RATE: f64 = 10_000.;
+///
+/// let record: Vec<Complex<f64>> = (0..LEN)
+///     .map(|i| Complex::from((i as f64 * 0.3).sin() + ((i * 7919) % 101) as f64 / 101.))
+///     .collect();
+///
+/// let extractor = CepstrumExtractor::new(LEN);
+/// let families = RahmonicDetector::new(SAMPLE_RATE).detect(&extractor.rceps_to_vec(&record));
+///
+/// let editor = families
+///     .iter()
+///     .fold(CepstralEditor::new(SAMPLE_RATE), |editor, family| editor.with_family(family, 2));
+///
+/// let edited = editor.edit_to_vec(&extractor, &record);
+/// assert_eq!(edited.len(), LEN);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CepstralEditor<T: CepFloat> {
+    sample_rate: T,
+    combs: Vec<Comb<T>>,
+    cutoff: Option<T>,
+}
+
+impl<T: CepFloat> CepstralEditor<T> {
+    /// Builds a new editor, leaving the cepstrum untouched, for records sampled at `sample_rate`.
+    pub fn new(sample_rate: T) -> Self {
+        Self {
+            sample_rate,
+            combs: vec![],
+            cutoff: None,
+        }
+    }
+
+    /// Removes `family`, i.e. every multiple of its fundamental, together with `width` bins on each
+    /// side.
+    pub fn with_family(self, family: &RahmonicFamily<T>, width: usize) -> Self {
+        self.with_quefrency(family.fundamental, width)
+    }
+
+    /// Removes every multiple of `quefrency` (in seconds), together with `width` bins on each side.
+    ///
+    /// Panics if `quefrency` is not greater than zero, or if it is shorter than one sample.
+    pub fn with_quefrency(mut self, quefrency: T, width: usize) -> Self {
+        assert!(quefrency > T::zero(), "Quefrency must be greater than zero");
+        assert!(
+            quefrency * self.sample_rate >= T::one(),
+            "Quefrency must be at least one sample"
+        );
+
+        self.combs.push(Comb {
+            period: quefrency * self.sample_rate,
+            width,
+        });
+        self
+    }
+
+    /// Removes all the quefrencies above `cutoff` (in seconds), i.e. keeps only the smooth spectral
+    /// envelope.
+    pub fn with_cutoff(mut self, cutoff: T) -> Self {
+        self.cutoff = Some(cutoff);
+        self
+    }
+
+    /// Lifter to be applied to a full (two-sided) cepstrum long `len`.
+    pub fn lifter(&self, len: usize) -> Vec<T> {
+        let mut lifter = vec![T::one(); len];
+        let half = len / 2;
+
+        let mut remove = |n: usize| {
+            if n > 0 && n <= half {
+                lifter[n] = T::zero();
+                lifter[(len - n) % len] = T::zero();
+            }
+        };
+
+        for comb in &self.combs {
+            let mut order = 1;
+            while comb.period * idx(order) <= idx(half + comb.width) {
+                let centre = (comb.period * idx(order)).round().to_usize().unwrap();
+                (centre.saturating_sub(comb.width)..=centre + comb.width).for_each(&mut remove);
+                order += 1;
+            }
+        }
+
+        if let Some(cutoff) = self.cutoff {
+            let first = (cutoff * self.sample_rate).ceil().to_usize().unwrap_or(0);
+            (first.max(1)..=half).for_each(&mut remove);
+        }
+
+        lifter
+    }
+
+    /// Edits `record`, which must be as long as the extractor, in place.
+    pub fn edit_mut(&self, extractor: &CepstrumExtractor<T>, record: &mut [Complex<T>]) {
+        self.edit_with_guard_mut(&mut extractor.default_instance(), record);
+    }
+
+    /// Edits `record`, which must be as long as the extractor, placing the result in a new vector.
+    pub fn edit_to_vec(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        record: &[Complex<T>],
+    ) -> Vec<Complex<T>> {
+        let mut copied = record.to_vec();
+
+        self.edit_with_guard_mut(&mut extractor.default_instance(), &mut copied);
+
+        copied
+    }
+
+    /// As [`Self::edit_mut`], but uses the extractor instance at index `instance`.
+    pub fn edit_with_instance_mut(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        record: &mut [Complex<T>],
+        instance: usize,
+    ) {
+        self.edit_with_guard_mut(&mut extractor.checkout(instance), record);
+    }
+
+    /// As [`Self::edit_to_vec`], but uses the extractor instance at index `instance`.
+    pub fn edit_with_instance_to_vec(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        record: &[Complex<T>],
+        instance: usize,
+    ) -> Vec<Complex<T>> {
+        let mut copied = record.to_vec();
+
+        self.edit_with_instance_mut(extractor, &mut copied, instance);
+
+        copied
+    }
+
+    /// As [`Self::edit_mut`], but uses the extractor instance held by `guard`.
+    pub fn edit_with_guard_mut(&self, guard: &mut InstanceGuard<'_, T>, record: &mut [Complex<T>]) {
+        let len = record.len();
+        assert_eq!(
+            len,
+            guard.win_len(),
+            "Record length differs from the extractor's one"
+        );
+
+        let norm = T::one() / idx(len);
+
+        guard.fft_mut(record);
+        let phases: Vec<T> = record.iter().map(|x| x.arg()).collect();
+
+        // Real cepstrum.
+        record.iter_mut().for_each(|x| {
+            *x = Complex::from(x.norm().max(T::min_positive_value()).ln());
+        });
+        guard.ifft_mut(record);
+
+        for (c, l) in record.iter_mut().zip(self.lifter(len)) {
+            *c = *c * l * norm;
+        }
+
+        // Edited log amplitude, with the original phase.
+        guard.fft_mut(record);
+        for (x, phase) in record.iter_mut().zip(phases) {
+            *x = Complex::from_polar(x.re.exp(), phase);
+        }
+
+        guard.ifft_mut(record);
+        record.iter_mut().for_each(|x| *x = *x * norm);
+    }
+
+    /// As [`Self::edit_to_vec`], but uses the extractor instance held by `guard`.
+    pub fn edit_with_guard_to_vec(
+        &self,
+        guard: &mut InstanceGuard<'_, T>,
+        record: &[Complex<T>],
+    ) -> Vec<Complex<T>> {
+        let mut copied = record.to_vec();
+
+        self.edit_with_guard_mut(guard, &mut copied);
+
+        copied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CepstralEditor;
+    use crate::CepstrumExtractor;
+    use crate::num_complex::Complex;
+    use crate::vibration::rahmonics::RahmonicDetector;
+
+    const LEN: usize = 4096;
+    const SAMPLE_RATE: f64 = 8192.;
+    /// Spacing, in bins, of the harmonics of the "shaft".
+    const SPACING: usize = 64;
+
+    fn harmonics_to_rest(extractor: &CepstrumExtractor<f64>, signal: &[Complex<f64>]) -> f64 {
+        let mut spectrum = signal.to_vec();
+        extractor.acquire().fft_mut(&mut spectrum);
+
+        let (harm, rest) =
+            spectrum[1..LEN / 2]
+                .iter()
+                .enumerate()
+                .fold((0., 0.), |(h, r), (k, x)| {
+                    if (k + 1) % SPACING == 0 {
+                        (h + x.norm_sqr(), r)
+                    } else {
+                        (h, r + x.norm_sqr())
+                    }
+                });
+
+        harm / rest
+    }
+
+    #[test]
+    fn removes_harmonics() {
+        let mut state = 12345u64;
+        let record: Vec<Complex<f64>> = (0..LEN)
+            .map(|n| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                let noise = (state >> 33) as f64 / (1u64 << 31) as f64 - 0.5;
+                let shaft: f64 = (1..LEN / 2 / SPACING)
+                    .map(|h| {
+                        (std::f64::consts::TAU * (h * SPACING * n) as f64 / LEN as f64).cos() * 5.
+                    })
+                    .sum();
+
+                Complex::from(noise + shaft)
+            })
+            .collect();
+
+        let extractor = CepstrumExtractor::new(LEN);
+        let families = RahmonicDetector::new(SAMPLE_RATE).detect(&extractor.rceps_to_vec(&record));
+        assert!((families[0].fundamental * SAMPLE_RATE - (LEN / SPACING) as f64).abs() < 0.5);
+
+        let edited = CepstralEditor::new(SAMPLE_RATE)
+            .with_family(&families[0], 1)
+            .edit_to_vec(&extractor, &record);
+
+        let before = harmonics_to_rest(&extractor, &record);
+        let after = harmonics_to_rest(&extractor, &edited);
+
+        assert!(after < before / 100., "{} vs {}", after, before);
+    }
+
+    #[test]
+    #[should_panic(expected = "Quefrency must be greater than zero")]
+    fn zero_quefrency() {
+        CepstralEditor::new(SAMPLE_RATE).with_quefrency(0., 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Quefrency must be greater than zero")]
+    fn negative_quefrency() {
+        CepstralEditor::new(SAMPLE_RATE).with_quefrency(-0.01, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Quefrency must be at least one sample")]
+    fn quefrency_below_one_sample() {
+        CepstralEditor::new(SAMPLE_RATE).with_quefrency(0.5 / SAMPLE_RATE, 1);
+    }
+
+    #[test]
+    fn quefrency_of_one_sample() {
+        let lifter = CepstralEditor::new(SAMPLE_RATE)
+            .with_quefrency(1. / SAMPLE_RATE, 0)
+            .lifter(16);
+
+        // Every quefrency but the zeroth one is a multiple of the period.
+        assert_eq!(lifter[0], 1.);
+        assert!(lifter[1..].iter().all(|l| *l == 0.), "{:?}", lifter);
+    }
+}
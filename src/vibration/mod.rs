@@ -0,0 +1,5 @@
+//! Machine condition monitoring tools, built on top of [`crate::CepstrumExtractor`].
+
+pub mod editing;
+pub mod prewhitening;
+pub mod rahmonics;
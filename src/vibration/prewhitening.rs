@@ -0,0 +1,180 @@
+//! Cepstrum pre-whitening (CPW) module.
+//!
+//! Setting the real cepstrum to zero everywhere except at quefrency zero, and recombining it with
+//! the original phase, flattens the amplitude spectrum while preserving the phase: discrete
+//! components and resonances are removed at once, and the impulsive content of the signal (e.g.
+//! bearing faults) stands out. The squared envelope spectrum of the whitened signal then shows the
+//! fault frequencies.
+//!
+//! More information at:
+//! - P. Borghesani, P. Pennacchi, R. B. Randall, N. Sawalhi, R. Ricci, "Application of cepstrum
+//!   pre-whitening for the diagnosis of bearing faults under variable speed conditions", MSSP 2013.
+
+use alloc::vec::Vec;
+
+use crate::dsp::idx;
+use crate::num_complex::Complex;
+use crate::{CepFloat, CepstrumExtractor, InstanceGuard, RealToComplex};
+
+/// Pre-whitens `signal`, whose length must match the one of `extractor`.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::vibration::prewhitening::{prewhiten, squared_envelope_spectrum};
+///
+/// let signal: Vec<f64> = (0..1000).map(|i| (i as f64 * 0.2).sin() + ((i * 31) % 7) as f64).collect();
+///
+/// let extractor = CepstrumExtractor::new(signal.len());
+/// let whitened = prewhiten(&extractor, &signal);
+/// let ses = squared_envelope_spectrum(&extractor, &whitened);
+///
+/// assert_eq!(whitened.len(), 1000);
+/// assert_eq!(ses.len(), 500);
+/// ```
+pub fn prewhiten<T: CepFloat>(extractor: &CepstrumExtractor<T>, signal: &[T]) -> Vec<T> {
+    prewhiten_with_guard(&mut extractor.default_instance(), signal)
+}
+
+/// As [`prewhiten`], but uses the extractor instance at index `instance`.
+pub fn prewhiten_with_instance<T: CepFloat>(
+    extractor: &CepstrumExtractor<T>,
+    signal: &[T],
+    instance: usize,
+) -> Vec<T> {
+    prewhiten_with_guard(&mut extractor.checkout(instance), signal)
+}
+
+/// As [`prewhiten`], but uses the extractor instance held by `guard`.
+pub fn prewhiten_with_guard<T: CepFloat>(guard: &mut InstanceGuard<'_, T>, signal: &[T]) -> Vec<T> {
+    let len = signal.len();
+    assert_eq!(
+        len,
+        guard.win_len(),
+        "Signal length differs from the extractor's one"
+    );
+
+    let mut spectrum = signal.to_complex_vec();
+    guard.fft_mut(&mut spectrum);
+
+    // Quefrency zero of the real cepstrum: mean of the log amplitude.
+    let c0 = spectrum.iter().fold(T::zero(), |acc, x| {
+        acc + x.norm().max(T::min_positive_value()).ln()
+    }) / idx(len);
+    let amplitude = c0.exp() / idx(len);
+
+    spectrum
+        .iter_mut()
+        .for_each(|x| *x = Complex::from_polar(amplitude, x.arg()));
+    guard.ifft_mut(&mut spectrum);
+
+    spectrum.iter().map(|x| x.re).collect()
+}
+
+/// Squared envelope spectrum of `signal`, whose length must match the one of `extractor`.
+///
+/// The envelope is obtained through the analytic signal; its square is transformed and the
+/// magnitude of the first `signal.len() / 2` bins is returned, normalised by the length of the
+/// signal (bin `k` corresponds to `k * sample_rate / signal.len()` Hz).
+pub fn squared_envelope_spectrum<T: CepFloat>(
+    extractor: &CepstrumExtractor<T>,
+    signal: &[T],
+) -> Vec<T> {
+    squared_envelope_spectrum_with_guard(&mut extractor.default_instance(), signal)
+}
+
+/// As [`squared_envelope_spectrum`], but uses the extractor instance at index `instance`.
+pub fn squared_envelope_spectrum_with_instance<T: CepFloat>(
+    extractor: &CepstrumExtractor<T>,
+    signal: &[T],
+    instance: usize,
+) -> Vec<T> {
+    squared_envelope_spectrum_with_guard(&mut extractor.checkout(instance), signal)
+}
+
+/// As [`squared_envelope_spectrum`], but uses the extractor instance held by `guard`.
+pub fn squared_envelope_spectrum_with_guard<T: CepFloat>(
+    guard: &mut InstanceGuard<'_, T>,
+    signal: &[T],
+) -> Vec<T> {
+    let len = signal.len();
+    assert_eq!(
+        len,
+        guard.win_len(),
+        "Signal length differs from the extractor's one"
+    );
+
+    let norm = T::one() / idx(len);
+
+    // Analytic signal: negative frequencies removed, positive ones doubled.
+    let mut analytic = signal.to_complex_vec();
+    guard.fft_mut(&mut analytic);
+    for (k, x) in analytic.iter_mut().enumerate() {
+        *x = if k == 0 || (len.is_multiple_of(2) && k == len / 2) {
+            *x * norm
+        } else if k < len.div_ceil(2) {
+            *x * (norm + norm)
+        } else {
+            Complex::from(T::zero())
+        };
+    }
+    guard.ifft_mut(&mut analytic);
+
+    analytic
+        .iter_mut()
+        .for_each(|x| *x = Complex::from(x.norm_sqr()));
+    guard.fft_mut(&mut analytic);
+
+    analytic[..len / 2]
+        .iter()
+        .map(|x| x.norm() * norm)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prewhiten, squared_envelope_spectrum};
+    use crate::CepstrumExtractor;
+    use crate::RealToComplex;
+
+    const LEN: usize = 4000;
+
+    #[test]
+    fn flat_spectrum_and_fault_frequency() {
+        // Impulses every 40 samples (with some jitter, as real faults) exciting a resonance, plus a
+        // strong tone.
+        let mut state = 42u64;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (state >> 33) as f64 / (1u64 << 31) as f64 - 0.5
+        };
+        let mut signal: Vec<f64> = (0..LEN)
+            .map(|n| 3. * (n as f64 * 0.05).sin() + next() * 0.01)
+            .collect();
+        for k in 0..LEN / 40 {
+            let start = k * 40 + (next() * 3.).round().max(0.) as usize;
+            for (i, s) in signal[start.min(LEN)..].iter_mut().take(40).enumerate() {
+                *s += (-(i as f64) / 4.).exp() * (i as f64 * 1.3).sin();
+            }
+        }
+
+        let extractor = CepstrumExtractor::new(LEN);
+        let whitened = prewhiten(&extractor, &signal);
+
+        let mut spectrum = whitened.to_complex_vec();
+        extractor.acquire().fft_mut(&mut spectrum);
+        let first = spectrum[1].norm();
+        assert!(
+            spectrum[1..]
+                .iter()
+                .all(|x| (x.norm() - first).abs() < 1e-9 * first.max(1.))
+        );
+
+        // The fault frequency is LEN / 40 = 100 bins.
+        let ses = squared_envelope_spectrum(&extractor, &whitened);
+        let peak = (1..ses.len())
+            .max_by(|a, b| ses[*a].total_cmp(&ses[*b]))
+            .unwrap();
+        assert!((99..=101).contains(&peak), "{}", peak);
+    }
+}
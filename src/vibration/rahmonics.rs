@@ -0,0 +1,272 @@
+//! Rahmonic family detection module.
+//!
+//! Periodic components of a vibration signal, such as the sidebands produced by a gear or a bearing
+//! fault, show up in the real cepstrum as families of evenly spaced peaks (rahmonics). The spacing
+//! of a family is the period of the modulation, so its inverse is the frequency spacing of the
+//! sidebands in the spectrum.
+//!
+//! More information at:
+//! - R. B. Randall, "Vibration-based Condition Monitoring", Wiley 2011, ch. 3.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::dsp::{cst, idx};
+use crate::{CepFloat, Coefficient};
+
+/// A single member of a rahmonic family.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rahmonic<T: CepFloat> {
+    /// Order of the rahmonic (`1` for the fundamental).
+    pub order: usize,
+    /// Index of the peak within the cepstrum.
+    pub index: usize,
+    /// Quefrency of the peak, in seconds.
+    pub quefrency: T,
+    /// Value of the cepstrum at the peak.
+    pub strength: T,
+}
+
+/// A family of evenly spaced rahmonics.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RahmonicFamily<T: CepFloat> {
+    /// Fundamental quefrency, in seconds, estimated by a least-squares fit of all the members.
+    pub fundamental: T,
+    /// Frequency spacing of the sidebands in the spectrum (`1 / fundamental`), in Hz.
+    pub spacing_frequency: T,
+    /// Members found, in ascending order.
+    pub members: Vec<Rahmonic<T>>,
+    /// Sum of the strengths of the members.
+    pub strength: T,
+}
+
+/// Detector of rahmonic families within a real cepstrum.
+///
+/// A candidate fundamental is any peak standing out of the cepstrum by more than `threshold`
+/// standard deviations. For every candidate, the peaks are searched around every multiple of it
+/// (within `tolerance` bins), refining the fundamental as members are found. A candidate becomes a
+/// family if its first `min_members` rahmonics are all found; higher ones may be missing.
+/// Candidates are examined from the lowest quefrency up, and multiples of families already found
+/// are discarded.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::num_complex::Complex;
+/// use cepstrum_extractor::vibration::rahmonics::RahmonicDetector;
+///
+/// const WIN_LEN: usize = 1024;
+/// const SAMPLE_RATE: f64 = 10_000.;
+///
+/// // An impulse every 50 samples, i.e. every 5 ms.
+/// let signal: Vec<Complex<f64>> = (0..WIN_LEN)
+///     .map(|i| Complex::from(if i % 50 == 0 { 1. } else { 0.01 * ((i * 37) % 11) as f64 }))
+///     .collect();
+///
+/// let extractor = CepstrumExtractor::new(WIN_LEN);
+/// let cepstrum = extractor.rceps_to_vec(&signal);
+///
+/// let families = RahmonicDetector::new(SAMPLE_RATE).detect(&cepstrum);
+/// assert!((families[0].fundamental - 0.005).abs() < 1e-4);
+/// assert!((families[0].spacing_frequency - 200.).abs() < 1.);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RahmonicDetector<T: CepFloat> {
+    sample_rate: T,
+    min_quefrency: T,
+    max_quefrency: T,
+    max_order: usize,
+    min_members: usize,
+    tolerance: usize,
+    threshold: T,
+}
+
+impl<T: CepFloat> RahmonicDetector<T> {
+    /// Builds a new detector for cepstra of signals sampled at `sample_rate`.
+    ///
+    /// By default, fundamentals are searched over the whole cepstrum, up to 10 rahmonics are
+    /// examined, at least 3 members are required, the tolerance is 1 bin and the threshold is
+    /// 2 standard deviations.
+    pub fn new(sample_rate: T) -> Self {
+        Self {
+            sample_rate,
+            min_quefrency: T::zero(),
+            max_quefrency: T::infinity(),
+            max_order: 10,
+            min_members: 3,
+            tolerance: 1,
+            threshold: cst(2.),
+        }
+    }
+
+    /// Restricts the search of fundamentals to `min..=max` seconds.
+    pub fn with_quefrency_range(mut self, min: T, max: T) -> Self {
+        self.min_quefrency = min;
+        self.max_quefrency = max;
+        self
+    }
+
+    /// Sets the highest order of the rahmonics examined.
+    pub fn with_max_order(mut self, max_order: usize) -> Self {
+        self.max_order = max_order;
+        self
+    }
+
+    /// Sets the minimum number of members of a family.
+    pub fn with_min_members(mut self, min_members: usize) -> Self {
+        self.min_members = min_members;
+        self
+    }
+
+    /// Sets the half-width, in bins, of the region searched around the expected position of a
+    /// rahmonic.
+    pub fn with_tolerance(mut self, tolerance: usize) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the number of standard deviations a peak must stand out of the cepstrum.
+    pub fn with_threshold(mut self, threshold: T) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Finds the rahmonic families within `cepstrum`, i.e. the meaningful half of a real cepstrum,
+    /// such as the one returned by [`crate::CepstrumExtractor::rceps_to_vec`].
+    ///
+    /// Families are sorted by ascending fundamental.
+    pub fn detect<C: Coefficient<T>>(&self, cepstrum: &[C]) -> Vec<RahmonicFamily<T>> {
+        let c: Vec<T> = cepstrum.iter().map(|c| c.value()).collect();
+        let len = c.len();
+
+        // The first bins hold the spectral envelope: skip them.
+        let first = (self.min_quefrency * self.sample_rate)
+            .ceil()
+            .to_usize()
+            .unwrap_or(0)
+            .max(self.tolerance + 2);
+        if first + 2 >= len {
+            return vec![];
+        }
+        let last = (self.max_quefrency * self.sample_rate)
+            .floor()
+            .to_usize()
+            .unwrap_or(usize::MAX)
+            .min(len - 2);
+
+        let n = idx::<T>(len - first);
+        let mean = c[first..].iter().fold(T::zero(), |acc, x| acc + *x) / n;
+        let std = (c[first..]
+            .iter()
+            .fold(T::zero(), |acc, x| acc + (*x - mean) * (*x - mean))
+            / n)
+            .sqrt();
+        let threshold = mean + self.threshold * std;
+
+        let is_peak = |i: usize| {
+            i > 0 && i + 1 < len && c[i] >= threshold && c[i] > c[i - 1] && c[i] >= c[i + 1]
+        };
+
+        let mut families: Vec<RahmonicFamily<T>> = vec![];
+
+        for candidate in (first..=last).filter(|i| is_peak(*i)) {
+            let q = idx::<T>(candidate);
+            if families.iter().any(|f| {
+                let ratio = q / (f.fundamental * self.sample_rate);
+                (ratio - ratio.round()).abs() * f.fundamental * self.sample_rate
+                    <= idx(self.tolerance)
+            }) {
+                continue;
+            }
+
+            let mut members = vec![];
+            let mut fundamental = q;
+            let mut strength = T::zero();
+
+            for order in 1..=self.max_order {
+                let centre = (fundamental * idx(order)).round().to_usize().unwrap();
+                if centre + self.tolerance + 1 >= len {
+                    break;
+                }
+
+                let best = (centre - self.tolerance..=centre + self.tolerance)
+                    .filter(|i| is_peak(*i))
+                    .max_by(|a, b| c[*a].partial_cmp(&c[*b]).unwrap());
+
+                if let Some(index) = best {
+                    strength = strength + c[index];
+                    members.push(Rahmonic {
+                        order,
+                        index,
+                        quefrency: idx::<T>(index) / self.sample_rate,
+                        strength: c[index],
+                    });
+
+                    // Least-squares fit of `index = order * fundamental`.
+                    let (num, den) = members.iter().fold((T::zero(), T::zero()), |(n, d), m| {
+                        (
+                            n + idx::<T>(m.order * m.index),
+                            d + idx::<T>(m.order * m.order),
+                        )
+                    });
+                    fundamental = num / den;
+                } else if order <= self.min_members {
+                    break;
+                }
+            }
+
+            if members.len() >= self.min_members {
+                let fundamental = fundamental / self.sample_rate;
+
+                families.push(RahmonicFamily {
+                    fundamental,
+                    spacing_frequency: T::one() / fundamental,
+                    members,
+                    strength,
+                });
+            }
+        }
+
+        families
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RahmonicDetector;
+    use crate::CepstrumExtractor;
+    use crate::num_complex::Complex;
+    use crate::num_traits::Zero;
+
+    const WIN_LEN: usize = 2048;
+    const SAMPLE_RATE: f64 = 20_000.;
+
+    /// Decaying impulse train with period `period`.
+    fn train(period: usize, decay: f64) -> Vec<Complex<f64>> {
+        let mut out = vec![Complex::zero(); WIN_LEN];
+        (0..WIN_LEN / period).for_each(|k| out[k * period] = Complex::from(decay.powi(k as i32)));
+        out
+    }
+
+    #[test]
+    fn two_families() {
+        let (a, b) = (train(40, 0.7), train(67, 0.6));
+
+        // Circular convolution: the cepstra of the two trains add up.
+        let signal: Vec<Complex<f64>> = (0..WIN_LEN)
+            .map(|n| {
+                (0..WIN_LEN).fold(Complex::zero(), |acc, k| {
+                    acc + a[k] * b[(n + WIN_LEN - k) % WIN_LEN]
+                })
+            })
+            .collect();
+
+        let extractor = CepstrumExtractor::new(WIN_LEN);
+        let families = RahmonicDetector::new(SAMPLE_RATE).detect(&extractor.rceps_to_vec(&signal));
+
+        assert_eq!(families.len(), 2);
+        assert!((families[0].fundamental * SAMPLE_RATE - 40.).abs() < 0.5);
+        assert!((families[1].fundamental * SAMPLE_RATE - 67.).abs() < 0.5);
+        assert!(families[0].strength > families[1].strength);
+    }
+}
@@ -0,0 +1,321 @@
+//! Cepstral peak prominence (CPP) and smoothed cepstral peak prominence (CPPS) module.
+//!
+//! The prominence is the height, in dB, of the cepstral peak within the pitch range above a
+//! regression line fitted through the cepstrum. Parameters default to the ones of Praat.
+//!
+//! More information at:
+//! - J. Hillenbrand, R. A. Cleveland, R. L. Erickson, "Acoustic correlates of breathy vocal
+//!   quality", JSHR 1994.
+//! - J. Hillenbrand, R. A. Houde, "Acoustic correlates of breathy vocal quality: dysphonic voices
+//!   and continuous speech", JSHR 1996.
+//! - <https://www.fon.hum.uva.nl/praat/manual/PowerCepstrogram__Get_CPPS___.html>
+
+use alloc::vec::Vec;
+
+use crate::dsp::{cst, idx};
+use crate::{CepFloat, Coefficient};
+
+/// Cepstral peak found by [`Cpp::compute`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prominence<T: CepFloat> {
+    /// Height of the peak above the regression line, in dB.
+    pub prominence: T,
+    /// Quefrency of the peak, in seconds (parabolically interpolated).
+    pub quefrency: T,
+    /// Fundamental frequency corresponding to the peak (`1 / quefrency`), in Hz.
+    pub f0: T,
+}
+
+/// CPP analyser, working on the real cepstra returned by [`crate::CepstrumExtractor`].
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::num_complex::Complex;
+/// use cepstrum_extractor::voice::cpp::Cpp;
+///
+/// const WIN_LEN: usize = 1024;
+/// const SAMPLE_RATE: f64 = 16_000.;
+///
+/// // A pulse train at 200 Hz.
+/// let frame: Vec<Complex<f64>> = (0..WIN_LEN)
+///     .map(|i| Complex::from(if i % 80 == 0 { 1. } else { 0.001 * ((i * 13) % 7) as f64 }))
+///     .collect();
+///
+/// let extractor = CepstrumExtractor::new(WIN_LEN);
+/// let peak = Cpp::new(SAMPLE_RATE).compute(&extractor.rceps_to_vec(&frame)).unwrap();
+///
+/// assert!((peak.f0 - 200.).abs() < 1.);
+/// assert!(peak.prominence > 10.);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cpp<T: CepFloat> {
+    sample_rate: T,
+    f0_min: T,
+    f0_max: T,
+    tilt_start: T,
+    tilt_end: Option<T>,
+}
+
+impl<T: CepFloat> Cpp<T> {
+    /// Builds a new analyser for cepstra of signals sampled at `sample_rate`.
+    ///
+    /// The peak is searched between `60` and `330` Hz, and the regression line is fitted from
+    /// `1` ms up to the end of the cepstrum.
+    pub fn new(sample_rate: T) -> Self {
+        Self {
+            sample_rate,
+            f0_min: cst(60.),
+            f0_max: cst(330.),
+            tilt_start: cst(0.001),
+            tilt_end: None,
+        }
+    }
+
+    /// Sets the pitch range, in Hz, the peak is searched in.
+    pub fn with_pitch_range(mut self, f0_min: T, f0_max: T) -> Self {
+        assert!(f0_min > T::zero() && f0_min < f0_max, "Invalid pitch range");
+        self.f0_min = f0_min;
+        self.f0_max = f0_max;
+        self
+    }
+
+    /// Sets the quefrency range, in seconds, the regression line is fitted over. If `end` is
+    /// `None`, the line is fitted up to the end of the cepstrum.
+    pub fn with_tilt_range(mut self, start: T, end: Option<T>) -> Self {
+        self.tilt_start = start;
+        self.tilt_end = end;
+        self
+    }
+
+    /// Converts `cepstrum` into dB, i.e. `20 * log10(|c|)`.
+    pub fn to_db<C: Coefficient<T>>(cepstrum: &[C]) -> Vec<T> {
+        cepstrum
+            .iter()
+            .map(|c| Self::db(c.value() * c.value()))
+            .collect()
+    }
+
+    #[inline]
+    fn db(power: T) -> T {
+        cst::<T>(10.) * power.max(T::min_positive_value()).log10()
+    }
+
+    /// Computes the prominence of the cepstral peak of `cepstrum`, i.e. the meaningful half of a
+    /// real cepstrum, such as the one returned by [`crate::CepstrumExtractor::rceps_to_vec`].
+    ///
+    /// Returns `None` if the pitch range does not fit into the cepstrum.
+    pub fn compute<C: Coefficient<T>>(&self, cepstrum: &[C]) -> Option<Prominence<T>> {
+        self.prominence(&Self::to_db(cepstrum))
+    }
+
+    /// As [`Self::compute`], but takes a cepstrum already in dB.
+    fn prominence(&self, db: &[T]) -> Option<Prominence<T>> {
+        let len = db.len();
+
+        let first = (self.sample_rate / self.f0_max).floor().to_usize()?.max(1);
+        let last = (self.sample_rate / self.f0_min)
+            .ceil()
+            .to_usize()?
+            .min(len.checked_sub(2)?);
+        if first > last {
+            return None;
+        }
+
+        let peak = (first..=last).max_by(|a, b| db[*a].partial_cmp(&db[*b]).unwrap())?;
+
+        // Parabolic interpolation of the peak.
+        let (l, c, r) = (db[peak - 1], db[peak], db[peak + 1]);
+        let den = l - c - c + r;
+        let (offset, value) = if den < T::zero() {
+            let offset = cst::<T>(0.5) * (l - r) / den;
+            (offset, c - cst::<T>(0.25) * (l - r) * offset)
+        } else {
+            (T::zero(), c)
+        };
+        let position = idx::<T>(peak) + offset;
+
+        // Least-squares regression line of the cepstrum over the tilt range.
+        let start = (self.tilt_start * self.sample_rate)
+            .round()
+            .to_usize()?
+            .min(len - 1);
+        let end = self
+            .tilt_end
+            .and_then(|e| (e * self.sample_rate).round().to_usize())
+            .unwrap_or(len - 1)
+            .min(len - 1);
+        if end <= start {
+            return None;
+        }
+
+        let n = idx::<T>(end - start + 1);
+        let (sx, sy, sxx, sxy) = (start..=end).fold(
+            (T::zero(), T::zero(), T::zero(), T::zero()),
+            |(sx, sy, sxx, sxy), i| {
+                let x = idx::<T>(i);
+                (sx + x, sy + db[i], sxx + x * x, sxy + x * db[i])
+            },
+        );
+        let slope = (n * sxy - sx * sy) / (n * sxx - sx * sx);
+        let intercept = (sy - slope * sx) / n;
+
+        let quefrency = position / self.sample_rate;
+
+        Some(Prominence {
+            prominence: value - (intercept + slope * position),
+            quefrency,
+            f0: T::one() / quefrency,
+        })
+    }
+}
+
+/// CPPS analyser: cepstra are smoothed across time and quefrency before computing the prominence.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::voice::cpp::{Cpp, Cpps};
+/// use cepstrum_extractor::windows::hann::Hann;
+///
+/// const WIN_LEN: usize = 1024;
+/// const HOP: usize = 32;
+/// const SAMPLE_RATE: f64 = 16_000.;
+///
+/// let signal: Vec<f64> = (0..16_000).map(|i| if i % 80 == 0 { 1. } else { 0. }).collect();
+///
+/// let extractor = CepstrumExtractor::new(WIN_LEN);
+/// let cepstrogram: Vec<_> = signal
+///     .windows(WIN_LEN)
+///     .step_by(HOP)
+///     .map(|frame| extractor.rceps_to_vec(&frame.hann_complex()))
+///     .collect();
+///
+/// let cpps = Cpps::new(Cpp::new(SAMPLE_RATE), HOP as f64 / SAMPLE_RATE);
+/// assert!(cpps.mean(&cepstrogram).unwrap() > 10.);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cpps<T: CepFloat> {
+    cpp: Cpp<T>,
+    time_frames: usize,
+    quefrency_bins: usize,
+}
+
+impl<T: CepFloat> Cpps<T> {
+    /// Builds a new analyser using `cpp` on cepstra `hop` seconds apart.
+    ///
+    /// Cepstra are averaged over `0.02` s in time and over `0.0005` s in quefrency.
+    pub fn new(cpp: Cpp<T>, hop: T) -> Self {
+        Self::with_smoothing(cpp, hop, cst(0.02), cst(0.0005))
+    }
+
+    /// As [`Self::new`], but cepstra are averaged over `time` seconds in time and over `quefrency`
+    /// seconds in quefrency.
+    pub fn with_smoothing(cpp: Cpp<T>, hop: T, time: T, quefrency: T) -> Self {
+        let odd = |x: T| {
+            let n = x.round().to_usize().unwrap_or(1).max(1);
+            n | 1
+        };
+
+        Self {
+            time_frames: odd(time / hop),
+            quefrency_bins: odd(quefrency * cpp.sample_rate),
+            cpp,
+        }
+    }
+
+    /// Computes the prominence of every frame of `cepstrogram`, a sequence of cepstra such as the
+    /// ones returned by [`crate::CepstrumExtractor::rceps_to_vec`], after smoothing.
+    pub fn compute<C, S>(&self, cepstrogram: &[S]) -> Vec<Option<Prominence<T>>>
+    where
+        C: Coefficient<T>,
+        S: AsRef<[C]>,
+    {
+        let power: Vec<Vec<T>> = cepstrogram
+            .iter()
+            .map(|c| c.as_ref().iter().map(|c| c.value() * c.value()).collect())
+            .collect();
+        let frames = power.len();
+        let t_half = self.time_frames / 2;
+        let q_half = self.quefrency_bins / 2;
+
+        (0..frames)
+            .map(|f| {
+                let range = f.saturating_sub(t_half)..(f + t_half + 1).min(frames);
+                let count = idx::<T>(range.len());
+                let len = power[range.clone()]
+                    .iter()
+                    .map(|p| p.len())
+                    .min()
+                    .unwrap_or(0);
+
+                let time_smoothed: Vec<T> = (0..len)
+                    .map(|i| {
+                        power[range.clone()]
+                            .iter()
+                            .fold(T::zero(), |acc, p| acc + p[i])
+                            / count
+                    })
+                    .collect();
+
+                let smoothed: Vec<T> = (0..len)
+                    .map(|i| {
+                        let r = i.saturating_sub(q_half)..(i + q_half + 1).min(len);
+                        let n = idx::<T>(r.len());
+                        Cpp::db(time_smoothed[r].iter().fold(T::zero(), |acc, p| acc + *p) / n)
+                    })
+                    .collect();
+
+                self.cpp.prominence(&smoothed)
+            })
+            .collect()
+    }
+
+    /// Mean of the prominences of [`Self::compute`], ignoring frames without a peak.
+    pub fn mean<C, S>(&self, cepstrogram: &[S]) -> Option<T>
+    where
+        C: Coefficient<T>,
+        S: AsRef<[C]>,
+    {
+        let (sum, count) = self
+            .compute(cepstrogram)
+            .iter()
+            .flatten()
+            .fold((T::zero(), 0), |(s, c), p| (s + p.prominence, c + 1));
+
+        (count > 0).then(|| sum / idx(count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cpp;
+    use crate::CepstrumExtractor;
+    use crate::num_complex::Complex;
+
+    const WIN_LEN: usize = 2048;
+    const SAMPLE_RATE: f64 = 16_000.;
+
+    fn frame(noise: f64) -> Vec<Complex<f64>> {
+        let mut state = 7u64;
+        (0..WIN_LEN)
+            .map(|i| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                let n = (state >> 33) as f64 / (1u64 << 31) as f64 - 0.5;
+                Complex::from(if i % 100 == 0 { 1. } else { 0. } + n * noise)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn breathiness_lowers_prominence() {
+        let extractor = CepstrumExtractor::new(WIN_LEN);
+        let cpp = Cpp::new(SAMPLE_RATE);
+
+        let clean = cpp.compute(&extractor.rceps_to_vec(&frame(0.01))).unwrap();
+        let noisy = cpp.compute(&extractor.rceps_to_vec(&frame(0.5))).unwrap();
+
+        assert!((clean.f0 - 160.).abs() < 1.);
+        assert!(clean.prominence > noisy.prominence);
+    }
+}
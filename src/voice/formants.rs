@@ -0,0 +1,478 @@
+//! Formant estimation module.
+//!
+//! Formants are estimated frame by frame either by peak picking on the cepstrally smoothed spectral
+//! envelope (the real cepstrum is liftered below the pitch period and transformed back), or from
+//! the roots of the LPC inverse filter. [`FormantTracker`] then links the candidates of consecutive
+//! frames into continuous tracks.
+//!
+//! More information at:
+//! - A. M. Noll, "Cepstrum pitch determination", JASA 1967.
+//! - R. C. Snell, F. Milinazzo, "Formant location from LPC analysis data", IEEE TSAP 1993.
+//! - <https://www.fon.hum.uva.nl/praat/manual/Sound__To_Formant__burg____.html>
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::dsp::{cst, idx, levinson, polynomial_roots};
+use crate::num_complex::Complex;
+use crate::{CepFloat, CepstrumExtractor, InstanceGuard};
+
+/// A formant candidate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Formant<T: CepFloat> {
+    /// Centre frequency, in Hz.
+    pub frequency: T,
+    /// Bandwidth, in Hz.
+    pub bandwidth: T,
+}
+
+/// Method used to estimate the formants.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Method<T: CepFloat> {
+    /// Peak picking on the cepstrally smoothed envelope: quefrencies from `cutoff` seconds on are
+    /// removed. Bandwidths are measured `3` dB below the peaks.
+    Cepstral { cutoff: T },
+    /// Roots of the LPC inverse filter of order `order`, computed with the autocorrelation method.
+    Lpc { order: usize },
+}
+
+/// Formant estimator.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::voice::formants::{Formants, Method};
+/// use cepstrum_extractor::windows::hann::Hann;
+///
+/// const WIN_LEN: usize = 512;
+/// const SAMPLE_RATE: f64 = 10_000.;
+///
+/// // Pulse train through a resonance at 1 kHz.
+/// let (r, theta) = (0.97_f64, std::f64::consts::TAU * 1000. / SAMPLE_RATE);
+/// let mut y = [0.; 2];
+/// let frame: Vec<f64> = (0..WIN_LEN)
+///     .map(|i| {
+///         let x = if i % 100 == 0 { 1. } else { 0. };
+///         let out = x + 2. * r * theta.cos() * y[0] - r * r * y[1];
+///         y = [out, y[0]];
+///         out
+///     })
+///     .collect();
+///
+/// let extractor = CepstrumExtractor::new(WIN_LEN);
+/// let formants = Formants::new(WIN_LEN, SAMPLE_RATE)
+///     .with_method(Method::Lpc { order: 4 })
+///     .compute(&extractor, &frame.hann_complex());
+///
+/// assert!((formants[0].frequency - 1000.).abs() < 50.);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Formants<T: CepFloat> {
+    win_len: usize,
+    sample_rate: T,
+    method: Method<T>,
+    max_formants: usize,
+    f_min: T,
+    f_max: T,
+    max_bandwidth: T,
+}
+
+impl<T: CepFloat> Formants<T> {
+    /// Builds a new estimator for frames long `win_len`, sampled at `sample_rate`.
+    ///
+    /// By default, the cepstral method with a cutoff of `2` ms is used, up to `5` formants between
+    /// `90` Hz and the Nyquist frequency are returned, and candidates wider than `600` Hz are
+    /// discarded.
+    pub fn new(win_len: usize, sample_rate: T) -> Self {
+        Self {
+            win_len,
+            sample_rate,
+            method: Method::Cepstral { cutoff: cst(0.002) },
+            max_formants: 5,
+            f_min: cst(90.),
+            f_max: sample_rate / cst(2.),
+            max_bandwidth: cst(600.),
+        }
+    }
+
+    /// Sets the estimation method.
+    pub fn with_method(mut self, method: Method<T>) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Sets the maximum number of formants returned.
+    pub fn with_max_formants(mut self, max_formants: usize) -> Self {
+        self.max_formants = max_formants;
+        self
+    }
+
+    /// Sets the range, in Hz, formants are searched in.
+    pub fn with_frequency_range(mut self, f_min: T, f_max: T) -> Self {
+        assert!(f_min < f_max, "Invalid frequency range");
+        self.f_min = f_min;
+        self.f_max = f_max;
+        self
+    }
+
+    /// Sets the widest bandwidth, in Hz, of a formant.
+    pub fn with_max_bandwidth(mut self, max_bandwidth: T) -> Self {
+        self.max_bandwidth = max_bandwidth;
+        self
+    }
+
+    /// Estimates the formants of `frame`, which should be already windowed (and pre-emphasised, if
+    /// needed). Formants are sorted by ascending frequency.
+    pub fn compute(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        frame: &[Complex<T>],
+    ) -> Vec<Formant<T>> {
+        self.compute_with_guard(&mut extractor.default_instance(), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance at index `instance`.
+    pub fn compute_with_instance(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        frame: &[Complex<T>],
+        instance: usize,
+    ) -> Vec<Formant<T>> {
+        self.compute_with_guard(&mut extractor.checkout(instance), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance held by `guard`.
+    pub fn compute_with_guard(
+        &self,
+        guard: &mut InstanceGuard<'_, T>,
+        frame: &[Complex<T>],
+    ) -> Vec<Formant<T>> {
+        assert_eq!(
+            guard.win_len(),
+            self.win_len,
+            "Extractor length differs from the estimator's one"
+        );
+        assert_eq!(
+            frame.len(),
+            self.win_len,
+            "Frame length differs from the estimator's one"
+        );
+
+        let mut formants = match self.method {
+            Method::Cepstral { cutoff } => self.cepstral(guard, frame, cutoff),
+            Method::Lpc { order } => self.lpc(frame, order),
+        };
+
+        formants.retain(|f| {
+            f.frequency >= self.f_min
+                && f.frequency <= self.f_max
+                && f.bandwidth <= self.max_bandwidth
+        });
+        formants.sort_by(|a, b| a.frequency.partial_cmp(&b.frequency).unwrap());
+        formants.truncate(self.max_formants);
+
+        formants
+    }
+
+    /// Log envelope, in dB, of `frame` over the bins `0..=win_len / 2`.
+    fn envelope(
+        &self,
+        guard: &mut InstanceGuard<'_, T>,
+        frame: &[Complex<T>],
+        cutoff: T,
+    ) -> Vec<T> {
+        let len = self.win_len;
+        let norm = T::one() / idx(len);
+        let cut = (cutoff * self.sample_rate)
+            .ceil()
+            .to_usize()
+            .unwrap_or(0)
+            .max(1);
+
+        let mut buf = frame.to_vec();
+        guard.fft_mut(&mut buf);
+        buf.iter_mut().for_each(|x| {
+            *x = Complex::from(x.norm().max(T::min_positive_value()).ln());
+        });
+        guard.ifft_mut(&mut buf);
+
+        for (n, c) in buf.iter_mut().enumerate() {
+            *c = if n < cut || len - n < cut {
+                *c * norm
+            } else {
+                Complex::from(T::zero())
+            };
+        }
+        guard.fft_mut(&mut buf);
+
+        let db = cst::<T>(20.) / T::LN_10();
+        buf[..=len / 2].iter().map(|x| x.re * db).collect()
+    }
+
+    fn cepstral(
+        &self,
+        guard: &mut InstanceGuard<'_, T>,
+        frame: &[Complex<T>],
+        cutoff: T,
+    ) -> Vec<Formant<T>> {
+        let env = self.envelope(guard, frame, cutoff);
+        let last = env.len() - 1;
+        let hz = self.sample_rate / idx(self.win_len);
+        let three = cst::<T>(3.);
+
+        // Distance, in bins, from `peak` to the point 3 dB below it, on either side. `None` if
+        // a valley or the edge is reached first.
+        let half_width = |peak: usize, level: T, forward: bool| {
+            let mut k = peak;
+            loop {
+                let next = if forward {
+                    (k < last).then(|| k + 1)?
+                } else {
+                    k.checked_sub(1)?
+                };
+                if env[next] <= level - three {
+                    let frac = (env[k] - (level - three)) / (env[k] - env[next]);
+                    let dist = idx::<T>(k.abs_diff(peak)) + frac;
+                    return Some(dist);
+                }
+                if env[next] > env[k] {
+                    return None;
+                }
+                k = next;
+            }
+        };
+
+        (1..last)
+            .filter(|k| env[*k] > env[k - 1] && env[*k] >= env[k + 1])
+            .filter_map(|k| {
+                let (l, c, r) = (env[k - 1], env[k], env[k + 1]);
+                let den = l - c - c + r;
+                let (offset, level) = if den < T::zero() {
+                    let offset = cst::<T>(0.5) * (l - r) / den;
+                    (offset, c - cst::<T>(0.25) * (l - r) * offset)
+                } else {
+                    (T::zero(), c)
+                };
+
+                let width = match (half_width(k, level, false), half_width(k, level, true)) {
+                    (Some(a), Some(b)) => a + b,
+                    (Some(a), None) | (None, Some(a)) => a + a,
+                    (None, None) => return None,
+                };
+
+                Some(Formant {
+                    frequency: (idx::<T>(k) + offset) * hz,
+                    bandwidth: width * hz,
+                })
+            })
+            .collect()
+    }
+
+    fn lpc(&self, frame: &[Complex<T>], order: usize) -> Vec<Formant<T>> {
+        let r: Vec<T> = (0..=order)
+            .map(|lag| {
+                frame[lag.min(frame.len())..]
+                    .iter()
+                    .zip(frame.iter())
+                    .fold(T::zero(), |acc, (a, b)| acc + a.re * b.re)
+            })
+            .collect();
+
+        let (a, err) = levinson(&r, order);
+        if err <= T::zero() {
+            return vec![];
+        }
+
+        polynomial_roots(&a)
+            .into_iter()
+            .filter(|z| z.im > T::zero())
+            .map(|z| Formant {
+                frequency: z.arg() * self.sample_rate / T::TAU(),
+                bandwidth: -z.norm().ln() * self.sample_rate / T::PI(),
+            })
+            .collect()
+    }
+}
+
+/// Tracker linking the formants of consecutive frames.
+///
+/// Every track has a reference frequency, i.e. its last value or, at the beginning, a nominal one.
+/// The candidates of a frame are assigned to the tracks, keeping their order, so that the sum of
+/// the relative deviations from the references is minimal; a track is left empty, at the cost of
+/// `max_jump`, rather than deviating more than that.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::voice::formants::{Formant, FormantTracker};
+///
+/// let formant = |frequency: f64| Formant { frequency, bandwidth: 80. };
+///
+/// let mut tracker = FormantTracker::new(3);
+/// let tracked = tracker.track(&[formant(480.), formant(1600.), formant(2500.)]);
+/// assert!(tracked.iter().all(|f| f.is_some()));
+///
+/// // The first formant is missing: the others keep their tracks.
+/// let tracked = tracker.track(&[formant(1580.), formant(2520.)]);
+/// assert!(tracked[0].is_none());
+/// assert_eq!(tracked[1].as_ref().unwrap().frequency, 1580.);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormantTracker<T: CepFloat> {
+    nominal: Vec<T>,
+    references: Vec<T>,
+    max_jump: T,
+}
+
+impl<T: CepFloat> FormantTracker<T> {
+    /// Builds a new tracker of `tracks` formants, with nominal frequencies `500`, `1500`, `2500`,
+    /// ... Hz (a neutral vowel) and a maximum relative jump of `0.5`.
+    pub fn new(tracks: usize) -> Self {
+        let nominal: Vec<T> = (0..tracks).map(|i| cst(500. + 1000. * i as f64)).collect();
+        Self::with_nominal(&nominal)
+    }
+
+    /// Builds a new tracker with the given nominal frequencies, in Hz, one per track.
+    pub fn with_nominal(nominal: &[T]) -> Self {
+        Self {
+            nominal: nominal.to_vec(),
+            references: nominal.to_vec(),
+            max_jump: cst(0.5),
+        }
+    }
+
+    /// Sets the maximum relative deviation of a formant from its reference.
+    pub fn with_max_jump(mut self, max_jump: T) -> Self {
+        self.max_jump = max_jump;
+        self
+    }
+
+    /// Resets the references to the nominal frequencies, e.g. at the beginning of a new utterance.
+    pub fn reset(&mut self) {
+        self.references.clone_from(&self.nominal);
+    }
+
+    /// Assigns the formants of the next frame, sorted by ascending frequency, to the tracks.
+    pub fn track(&mut self, candidates: &[Formant<T>]) -> Vec<Option<Formant<T>>> {
+        let (n, m) = (self.references.len(), candidates.len());
+        let cost = |i: usize, j: usize| {
+            (candidates[j].frequency - self.references[i]).abs() / self.references[i]
+        };
+
+        // `dp[i][j]`: best cost of the first `i` tracks using the first `j` candidates.
+        let mut dp = vec![vec![T::infinity(); m + 1]; n + 1];
+        dp[0].iter_mut().for_each(|d| *d = T::zero());
+        for i in 1..=n {
+            dp[i][0] = dp[i - 1][0] + self.max_jump;
+            for j in 1..=m {
+                let mut best = dp[i][j - 1].min(dp[i - 1][j] + self.max_jump);
+                let c = cost(i - 1, j - 1);
+                if c <= self.max_jump {
+                    best = best.min(dp[i - 1][j - 1] + c);
+                }
+                dp[i][j] = best;
+            }
+        }
+
+        let mut tracked = vec![None; n];
+        let (mut i, mut j) = (n, m);
+        while i > 0 {
+            if j > 0 && dp[i][j] == dp[i][j - 1] {
+                j -= 1;
+            } else if dp[i][j] == dp[i - 1][j] + self.max_jump {
+                i -= 1;
+            } else {
+                tracked[i - 1] = Some(candidates[j - 1].clone());
+                self.references[i - 1] = candidates[j - 1].frequency;
+                i -= 1;
+                j -= 1;
+            }
+        }
+
+        tracked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Formant, FormantTracker, Formants, Method};
+    use crate::CepstrumExtractor;
+    use crate::windows::hann::Hann;
+
+    const WIN_LEN: usize = 1024;
+    const SAMPLE_RATE: f64 = 10_000.;
+    const FORMANTS: [f64; 3] = [500., 1500., 2500.];
+
+    /// Pulse train at 100 Hz through a cascade of resonators at `FORMANTS`, pre-emphasised.
+    fn vowel() -> Vec<f64> {
+        let mut signal: Vec<f64> = (0..WIN_LEN)
+            .map(|i| if i % 100 == 0 { 1. } else { 0. })
+            .collect();
+
+        for f in FORMANTS {
+            let r = (-std::f64::consts::PI * 80. / SAMPLE_RATE).exp();
+            let theta = std::f64::consts::TAU * f / SAMPLE_RATE;
+            let mut y = [0.; 2];
+            signal.iter_mut().for_each(|x| {
+                let out = *x + 2. * r * theta.cos() * y[0] - r * r * y[1];
+                y = [out, y[0]];
+                *x = out;
+            });
+        }
+
+        (0..WIN_LEN)
+            .rev()
+            .for_each(|i| signal[i] -= if i > 0 { 0.97 * signal[i - 1] } else { 0. });
+
+        signal
+    }
+
+    #[test]
+    fn synthetic_vowel() {
+        let extractor = CepstrumExtractor::new(WIN_LEN);
+        let frame = vowel().hann_complex();
+
+        for (method, tolerance) in [
+            (Method::Cepstral { cutoff: 0.002 }, 100.),
+            (Method::Lpc { order: 10 }, 50.),
+        ] {
+            let formants = Formants::new(WIN_LEN, SAMPLE_RATE)
+                .with_method(method.clone())
+                .with_max_formants(3)
+                .compute(&extractor, &frame);
+
+            assert_eq!(formants.len(), 3, "{:?}: {:?}", method, formants);
+            for (f, expected) in formants.iter().zip(FORMANTS) {
+                assert!(
+                    (f.frequency - expected).abs() < tolerance,
+                    "{:?}: {:?}",
+                    method,
+                    formants
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tracking_continuity() {
+        let formant = |frequency: f64| Formant {
+            frequency,
+            bandwidth: 80.,
+        };
+        let mut tracker = FormantTracker::new(2);
+
+        // F2 glides down towards a low F1, which then disappears for a frame.
+        tracker.track(&[formant(700.), formant(1200.)]);
+        tracker.track(&[formant(650.), formant(1000.)]);
+        let tracked = tracker.track(&[formant(950.)]);
+
+        assert!(tracked[0].is_none());
+        assert_eq!(tracked[1].as_ref().unwrap().frequency, 950.);
+    }
+
+    #[test]
+    #[should_panic(expected = "Extractor length differs from the estimator's one")]
+    fn mismatched_extractor() {
+        let extractor = CepstrumExtractor::new(WIN_LEN * 2);
+        Formants::new(WIN_LEN, SAMPLE_RATE).compute(&extractor, &vowel().hann_complex());
+    }
+}
@@ -0,0 +1,331 @@
+//! Cepstrum-based harmonics-to-noise ratio (HNR) module.
+//!
+//! The rahmonics at the pitch period are liftered out of the real cepstrum of a frame; transforming
+//! back gives the log spectrum of the noise, and the harmonic spectrum is what remains of the
+//! original one.
+//!
+//! More information at:
+//! - G. de Krom, "A cepstrum-based technique for determining a harmonics-to-noise ratio in speech
+//!   signals", JSHR 1993.
+
+use alloc::vec::Vec;
+
+use crate::dsp::{cst, idx};
+use crate::num_complex::Complex;
+use crate::vibration::editing::CepstralEditor;
+use crate::voice::cpp::Cpp;
+use crate::{CepFloat, CepstrumExtractor, InstanceGuard};
+
+/// HNR of a single frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HnrFrame<T: CepFloat> {
+    /// Fundamental frequency used to lifter the cepstrum, in Hz.
+    pub f0: T,
+    /// HNR over the whole spectrum, in dB.
+    pub hnr: T,
+    /// HNR of every band passed to [`Hnr::with_bands`], in dB.
+    pub bands: Vec<T>,
+    /// Power spectrum of the harmonic part, for bins `0..=win_len / 2`.
+    pub harmonic: Vec<T>,
+    /// Power spectrum of the noise, for bins `0..=win_len / 2`.
+    pub noise: Vec<T>,
+}
+
+/// HNR estimator.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::voice::hnr::Hnr;
+/// use cepstrum_extractor::windows::hann::Hann;
+///
+/// const WIN_LEN: usize = 1024;
+/// const SAMPLE_RATE: f64 = 16_000.;
+///
+/// let frame: Vec<f64> = (0..WIN_LEN)
+///     .map(|i| if i % 100 == 0 { 1. } else { 0.01 * ((i * 7919) % 13) as f64 })
+///     .collect();
+///
+/// let extractor = CepstrumExtractor::new(WIN_LEN);
+/// let hnr = Hnr::new(WIN_LEN, SAMPLE_RATE).compute(&extractor, &frame.hann_complex()).unwrap();
+///
+/// assert!((hnr.f0 - 160.).abs() < 1.);
+/// assert_eq!(hnr.bands.len(), 4);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hnr<T: CepFloat> {
+    win_len: usize,
+    sample_rate: T,
+    width: usize,
+    bands: Vec<(T, T)>,
+    cpp: Cpp<T>,
+}
+
+impl<T: CepFloat> Hnr<T> {
+    /// Builds a new estimator for frames long `win_len`, sampled at `sample_rate`.
+    ///
+    /// By default, the pitch is searched between `60` and `330` Hz, every rahmonic is removed
+    /// together with `1` bin on each side, and the bands are `0-500`, `0-1500`, `0-2500` and
+    /// `0-3500` Hz, as in de Krom's paper.
+    pub fn new(win_len: usize, sample_rate: T) -> Self {
+        Self {
+            win_len,
+            sample_rate,
+            width: 1,
+            bands: [500., 1500., 2500., 3500.]
+                .iter()
+                .map(|f| (T::zero(), cst(*f)))
+                .collect(),
+            cpp: Cpp::new(sample_rate),
+        }
+    }
+
+    /// Sets the pitch range, in Hz, the pitch is searched in.
+    pub fn with_pitch_range(mut self, f0_min: T, f0_max: T) -> Self {
+        self.cpp = self.cpp.with_pitch_range(f0_min, f0_max);
+        self
+    }
+
+    /// Sets the number of bins removed on each side of every rahmonic.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets the frequency bands, in Hz, the HNR is computed over.
+    pub fn with_bands(mut self, bands: &[(T, T)]) -> Self {
+        self.bands = bands.to_vec();
+        self
+    }
+
+    /// Computes the HNR of `frame`, which should be already windowed, detecting its pitch from the
+    /// cepstral peak.
+    ///
+    /// Returns `None` if no peak is found.
+    pub fn compute(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        frame: &[Complex<T>],
+    ) -> Option<HnrFrame<T>> {
+        self.compute_with_guard(&mut extractor.default_instance(), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance at index `instance`.
+    pub fn compute_with_instance(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        frame: &[Complex<T>],
+        instance: usize,
+    ) -> Option<HnrFrame<T>> {
+        self.compute_with_guard(&mut extractor.checkout(instance), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance held by `guard`.
+    pub fn compute_with_guard(
+        &self,
+        guard: &mut InstanceGuard<'_, T>,
+        frame: &[Complex<T>],
+    ) -> Option<HnrFrame<T>> {
+        assert_eq!(
+            guard.win_len(),
+            self.win_len,
+            "Extractor length differs from the estimator's one"
+        );
+
+        let cepstrum = guard.rceps_to_vec(frame);
+        let f0 = self.cpp.compute(&cepstrum)?.f0;
+
+        Some(self.compute_with_f0_with_guard(guard, frame, f0))
+    }
+
+    /// Computes the HNR of `frame`, which should be already windowed, given its fundamental
+    /// frequency `f0`.
+    ///
+    /// Panics if `f0` is not positive and finite, or if it exceeds half the sample rate.
+    pub fn compute_with_f0(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        frame: &[Complex<T>],
+        f0: T,
+    ) -> HnrFrame<T> {
+        self.compute_with_f0_with_guard(&mut extractor.default_instance(), frame, f0)
+    }
+
+    /// As [`Self::compute_with_f0`], but uses the extractor instance at index `instance`.
+    pub fn compute_with_f0_with_instance(
+        &self,
+        extractor: &CepstrumExtractor<T>,
+        frame: &[Complex<T>],
+        f0: T,
+        instance: usize,
+    ) -> HnrFrame<T> {
+        self.compute_with_f0_with_guard(&mut extractor.checkout(instance), frame, f0)
+    }
+
+    /// As [`Self::compute_with_f0`], but uses the extractor instance held by `guard`.
+    pub fn compute_with_f0_with_guard(
+        &self,
+        guard: &mut InstanceGuard<'_, T>,
+        frame: &[Complex<T>],
+        f0: T,
+    ) -> HnrFrame<T> {
+        let len = self.win_len;
+        assert_eq!(
+            guard.win_len(),
+            len,
+            "Extractor length differs from the estimator's one"
+        );
+        assert_eq!(
+            frame.len(),
+            len,
+            "Frame length differs from the estimator's one"
+        );
+        assert!(
+            f0 > T::zero() && f0.is_finite(),
+            "Fundamental frequency must be positive and finite"
+        );
+        assert!(
+            f0 <= self.sample_rate / cst(2.),
+            "Fundamental frequency must not exceed the Nyquist frequency"
+        );
+
+        let norm = T::one() / idx(len);
+        let half = len / 2;
+
+        let mut buf = frame.to_vec();
+        guard.fft_mut(&mut buf);
+        let power: Vec<T> = buf[..=half].iter().map(|x| x.norm_sqr()).collect();
+
+        // Real cepstrum, without the rahmonics: what is left is the log spectrum of the noise.
+        buf.iter_mut()
+            .for_each(|x| *x = Complex::from(x.norm().max(T::min_positive_value()).ln()));
+        guard.ifft_mut(&mut buf);
+
+        let lifter = CepstralEditor::new(self.sample_rate)
+            .with_quefrency(T::one() / f0, self.width)
+            .lifter(len);
+        buf.iter_mut()
+            .zip(lifter)
+            .for_each(|(c, l)| *c = *c * l * norm);
+
+        guard.fft_mut(&mut buf);
+
+        let noise: Vec<T> = buf[..=half].iter().map(|x| (x.re + x.re).exp()).collect();
+        let harmonic: Vec<T> = power
+            .iter()
+            .zip(noise.iter())
+            .map(|(p, n)| (*p - *n).max(T::zero()))
+            .collect();
+
+        let ratio = |from: usize, to: usize| {
+            let (h, n) = (from..=to.min(half)).fold((T::zero(), T::zero()), |(h, n), k| {
+                (h + harmonic[k], n + noise[k])
+            });
+            cst::<T>(10.)
+                * (h.max(T::min_positive_value()) / n.max(T::min_positive_value())).log10()
+        };
+
+        let bin = |f: T| {
+            (f * idx(len) / self.sample_rate)
+                .round()
+                .to_usize()
+                .unwrap_or(0)
+        };
+        let bands = self
+            .bands
+            .iter()
+            .map(|(lo, hi)| ratio(bin(*lo), bin(*hi)))
+            .collect();
+
+        HnrFrame {
+            f0,
+            hnr: ratio(0, half),
+            bands,
+            harmonic,
+            noise,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Hnr;
+    use crate::CepstrumExtractor;
+    use crate::windows::hann::Hann;
+
+    const WIN_LEN: usize = 2048;
+    const SAMPLE_RATE: f64 = 16_000.;
+
+    fn hnr(noise: f64) -> f64 {
+        let mut state = 3u64;
+        let frame: Vec<f64> = (0..WIN_LEN)
+            .map(|i| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                let n = (state >> 33) as f64 / (1u64 << 31) as f64 - 0.5;
+                (0..20)
+                    .map(|h| (i as f64 * 0.0625 * h as f64).cos())
+                    .sum::<f64>()
+                    + n * noise
+            })
+            .collect();
+
+        let extractor = CepstrumExtractor::new(WIN_LEN);
+        let hnr = Hnr::new(WIN_LEN, SAMPLE_RATE)
+            .compute(&extractor, &frame.hann_complex())
+            .unwrap();
+
+        assert!((hnr.f0 - 159.15).abs() < 1.5, "{}", hnr.f0);
+        hnr.hnr
+    }
+
+    #[test]
+    fn noise_lowers_hnr() {
+        let (clean, noisy) = (hnr(0.5), hnr(10.));
+
+        assert!(clean > noisy + 10., "{} vs {}", clean, noisy);
+    }
+
+    #[test]
+    #[should_panic(expected = "Fundamental frequency must be positive and finite")]
+    fn zero_f0() {
+        let extractor = CepstrumExtractor::new(WIN_LEN);
+        Hnr::new(WIN_LEN, SAMPLE_RATE).compute_with_f0(
+            &extractor,
+            &[0f64; WIN_LEN].hann_complex(),
+            0.,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Fundamental frequency must be positive and finite")]
+    fn infinite_f0() {
+        let extractor = CepstrumExtractor::new(WIN_LEN);
+        Hnr::new(WIN_LEN, SAMPLE_RATE).compute_with_f0(
+            &extractor,
+            &[0f64; WIN_LEN].hann_complex(),
+            f64::INFINITY,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Fundamental frequency must not exceed the Nyquist frequency")]
+    fn f0_above_nyquist() {
+        let extractor = CepstrumExtractor::new(WIN_LEN);
+        Hnr::new(WIN_LEN, SAMPLE_RATE).compute_with_f0(
+            &extractor,
+            &[0f64; WIN_LEN].hann_complex(),
+            SAMPLE_RATE,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Extractor length differs from the estimator's one")]
+    fn mismatched_extractor() {
+        let extractor = CepstrumExtractor::new(WIN_LEN / 2);
+        Hnr::new(WIN_LEN, SAMPLE_RATE).compute_with_f0(
+            &extractor,
+            &[0f64; WIN_LEN].hann_complex(),
+            100.,
+        );
+    }
+}
@@ -0,0 +1,5 @@
+//! Voice analysis tools, built on top of [`crate::CepstrumExtractor`].
+
+pub mod cpp;
+pub mod formants;
+pub mod hnr;
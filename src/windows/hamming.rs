@@ -4,9 +4,10 @@
 //! - <https://it.mathworks.com/help/signal/ref/hamming.html>
 //! - <https://numpy.org/doc/stable/reference/generated/numpy.hamming.html>
 
-use std::convert::From;
+use alloc::vec::Vec;
+use core::convert::From;
 
-use rustfft::num_complex::Complex;
+use crate::num_complex::Complex;
 
 use crate::num_traits::{AsPrimitive, Float, FloatConst};
 
@@ -96,7 +97,7 @@ where
     #[inline]
     fn hamming(&self) -> Vec<Complex<T>> {
         self.iter().enumerate().fold(Vec::with_capacity(self.len()), |mut acc, (i, sample)| {
-            let mut el = sample.clone();
+            let mut el = *sample;
             el.re = _hamming(&sample.re, i, self.len());
             acc.push(el);
 
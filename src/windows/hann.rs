@@ -2,9 +2,10 @@
 //!
 //! More information at <https://it.mathworks.com/help/signal/ref/hann.html>.
 
-use std::convert::From;
+use alloc::vec::Vec;
+use core::convert::From;
 
-use rustfft::num_complex::Complex;
+use crate::num_complex::Complex;
 
 use crate::num_traits::{AsPrimitive, Float, FloatConst};
 
@@ -94,7 +95,7 @@ impl<T: Float + FloatConst + 'static> HannComplex<T> for [Complex<T>]
     #[inline]
     fn hann(&self) -> Vec<Complex<T>> {
         self.iter().enumerate().fold(Vec::with_capacity(self.len()), |mut acc, (i, sample)| {
-            let mut el = sample.clone();
+            let mut el = *sample;
             el.re = _hann(&sample.re, i, self.len());
             acc.push(el);
 
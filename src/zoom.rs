@@ -0,0 +1,241 @@
+//! Zoomed real cepstrum module.
+//!
+//! The inverse transform of the log spectrum is evaluated over a chosen quefrency range only, at
+//! an arbitrary resolution, with the chirp-z transform computed through Bluestein's algorithm: a
+//! circular convolution, carried out with the FFTs of the extractor.
+//!
+//! More information at:
+//! - L. Rabiner, R. Schafer, C. Rader, "The chirp z-transform algorithm", IEEE Transactions on
+//!   Audio and Electroacoustics 1969.
+//! - <https://en.wikipedia.org/wiki/Chirp_Z-transform>
+
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::backend::FftBackend;
+use crate::dsp::{cst, idx};
+use crate::num_complex::Complex;
+use crate::num_traits::{Euclid, Zero};
+use crate::{CepFloat, CepstrumExtractor, InstanceGuard};
+
+/// Zoom-cepstrum analyser: the real cepstrum of a frame at evenly spaced quefrencies, which need
+/// not be multiples of the sampling period.
+///
+/// The values are the band-limited interpolation of the real cepstrum, so at integer quefrencies
+/// (in samples) they are the same as the ones of [`CepstrumExtractor::rceps_to_vec`], scaling
+/// included.
+///
+/// ## Examples
+/// ```rust
+/// use cepstrum_extractor::CepstrumExtractor;
+/// use cepstrum_extractor::windows::hann::Hann;
+/// use cepstrum_extractor::zoom::ZoomCepstrum;
+///
+/// const WIN_LEN: usize = 1024;
+///
+/// let extractor = CepstrumExtractor::new(WIN_LEN);
+/// // From 2 ms to 20 ms, every 10 µs.
+/// let zoom = ZoomCepstrum::new(&extractor, 16_000., 0.002, 0.02, 1801);
+///
+/// let ceps = zoom.compute(&extractor, &[0.1f32; WIN_LEN].hann_complex());
+/// assert_eq!(ceps.len(), zoom.quefrencies().len());
+/// ```
+pub struct ZoomCepstrum<T: CepFloat> {
+    win_len: usize,
+    quefrencies: Vec<T>,
+    /// Plan of the circular convolution, from the cache of the extractor.
+    plan: Arc<dyn FftBackend<T>>,
+    /// Chirp the log spectrum is multiplied by, including the weights of the symmetric bins.
+    pre: Vec<Complex<T>>,
+    /// Spectrum of the chirp the product is convolved with.
+    kernel: Vec<Complex<T>>,
+    /// Chirp the convolution is multiplied by, including the normalisation of the inverse FFT.
+    post: Vec<Complex<T>>,
+}
+
+impl<T: CepFloat> ZoomCepstrum<T> {
+    /// Builds an analyser for the window length of `extractor`, evaluating `points` quefrencies
+    /// evenly spaced from `min_quefrency` to `max_quefrency`, both included and in seconds.
+    pub fn new(
+        extractor: &CepstrumExtractor<T>,
+        sample_rate: T,
+        min_quefrency: T,
+        max_quefrency: T,
+        points: usize,
+    ) -> Self {
+        assert!(points >= 2, "At least two quefrencies are needed");
+        assert!(
+            min_quefrency < max_quefrency,
+            "The quefrency range is empty"
+        );
+
+        let win_len = extractor.win_len();
+        let bins = win_len / 2 + 1;
+        let conv_len = (bins + points - 1).next_power_of_two();
+        let plan = extractor.plan(conv_len);
+
+        let step = (max_quefrency - min_quefrency) / idx(points - 1);
+        let quefrencies: Vec<T> = (0..points).map(|m| min_quefrency + step * idx(m)).collect();
+
+        // Start and step in samples; phases are reduced in f64 so that they stay accurate for `f32`.
+        let n = win_len as f64;
+        let start = (min_quefrency * sample_rate).to_f64().unwrap();
+        let step = (step * sample_rate).to_f64().unwrap();
+        let tau = 2. * core::f64::consts::PI;
+        let wrap = |phase: f64| Euclid::rem_euclid(&phase, &tau);
+        let chirp = |j: f64| wrap(core::f64::consts::PI * step * j * j / n);
+        let polar = |phase: f64| Complex::from_polar(T::one(), cst(phase));
+
+        let pre = (0..bins)
+            .map(|k| {
+                let weight = if k == 0 || 2 * k == win_len { 1 } else { 2 };
+                let phase = wrap(tau * k as f64 * start / n) + chirp(k as f64);
+                polar(phase) * idx::<T>(weight)
+            })
+            .collect();
+
+        let mut kernel = vec![Complex::zero(); conv_len];
+        kernel[..points]
+            .iter_mut()
+            .enumerate()
+            .for_each(|(m, h)| *h = polar(-chirp(m as f64)));
+        for k in 1..bins {
+            kernel[conv_len - k] = polar(-chirp(k as f64));
+        }
+        extractor
+            .acquire()
+            .fft_with_plan_mut(plan.as_ref(), &mut kernel);
+
+        let post = (0..points)
+            .map(|m| polar(chirp(m as f64)) / idx::<T>(conv_len))
+            .collect();
+
+        Self {
+            win_len,
+            quefrencies,
+            plan,
+            pre,
+            kernel,
+            post,
+        }
+    }
+
+    /// Quefrencies, in seconds, the cepstrum is evaluated at.
+    pub fn quefrencies(&self) -> &[T] {
+        &self.quefrencies
+    }
+
+    /// Computes the real cepstrum of `frame`, which should be already windowed, at every
+    /// quefrency in [`Self::quefrencies`].
+    pub fn compute(&self, extractor: &CepstrumExtractor<T>, frame: &[Complex<T>]) -> Vec<T> {
+        self.compute_with_guard(&mut extractor.default_instance(), frame)
+    }
+
+    /// As [`Self::compute`], but uses the extractor instance held by `guard`.
+    pub fn compute_with_guard(
+        &self,
+        guard: &mut InstanceGuard<'_, T>,
+        frame: &[Complex<T>],
+    ) -> Vec<T> {
+        assert_eq!(
+            frame.len(),
+            self.win_len,
+            "Frame length differs from the analyser's one"
+        );
+        assert_eq!(
+            guard.win_len(),
+            self.win_len,
+            "Extractor length differs from the analyser's one"
+        );
+
+        let mut spectrum = frame.to_vec();
+        guard.fft_mut(&mut spectrum);
+        T::log_magnitude(&mut spectrum[..self.pre.len()]);
+
+        let mut conv = vec![Complex::zero(); self.plan.len()];
+        conv.iter_mut()
+            .zip(spectrum.iter().zip(self.pre.iter()))
+            .for_each(|(c, (x, p))| *c = p * x.re);
+
+        guard.fft_with_plan_mut(self.plan.as_ref(), &mut conv);
+        conv.iter_mut()
+            .zip(self.kernel.iter())
+            .for_each(|(c, k)| *c = *c * k);
+        guard.ifft_with_plan_mut(self.plan.as_ref(), &mut conv);
+
+        conv.iter()
+            .zip(self.post.iter())
+            .map(|(c, p)| (c * p).re)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ZoomCepstrum;
+    use crate::num_complex::Complex;
+    use crate::{CepstrumExtractor, RealToComplex};
+
+    const WIN_LEN: usize = 512;
+    const SAMPLE_RATE: f64 = 16_000.;
+
+    fn harmonics(f0: f64) -> Vec<f64> {
+        (0..WIN_LEN)
+            .map(|i| {
+                let t = i as f64 / SAMPLE_RATE;
+                (1..20)
+                    .map(|h| (2. * std::f64::consts::PI * f0 * h as f64 * t).sin() / h as f64)
+                    .sum::<f64>()
+                    + 0.01 * ((i * 7919) % 127) as f64 / 127.
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_rceps_at_integer_quefrencies() {
+        let extractor: CepstrumExtractor<f64> = CepstrumExtractor::new(WIN_LEN);
+        let signal = harmonics(217.).to_complex_vec();
+
+        // Samples 10 to 200, one by one.
+        let zoom = ZoomCepstrum::new(
+            &extractor,
+            SAMPLE_RATE,
+            10. / SAMPLE_RATE,
+            200. / SAMPLE_RATE,
+            191,
+        );
+        let zoomed = zoom.compute(&extractor, &signal);
+        let ceps = extractor.rceps_to_vec(&signal);
+
+        for (z, c) in zoomed.iter().zip(&ceps[10..=200]) {
+            assert!(
+                (z - c.re).abs() < 1e-8 * c.norm().max(1.),
+                "{z} != {}",
+                c.re
+            );
+        }
+    }
+
+    #[test]
+    fn finer_than_bins() {
+        let extractor: CepstrumExtractor<f32> = CepstrumExtractor::new(WIN_LEN);
+        let signal: Vec<Complex<f32>> = harmonics(217.)
+            .iter()
+            .map(|s| Complex::from(*s as f32))
+            .collect();
+
+        let zoom = ZoomCepstrum::new(&extractor, SAMPLE_RATE as f32, 0.002, 0.02, 1801);
+        let zoomed = zoom.compute_with_guard(&mut extractor.acquire(), &signal);
+
+        let peak = zoomed
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(m, _)| zoom.quefrencies()[m])
+            .unwrap();
+
+        // Closer to the period than half a sample, the resolution of the plain cepstrum.
+        assert!((peak as f64 - 1. / 217.).abs() < 0.25 / SAMPLE_RATE);
+    }
+}
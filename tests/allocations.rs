@@ -0,0 +1,108 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use cepstrum_extractor::CepstrumExtractor;
+use cepstrum_extractor::num_complex::Complex;
+
+const WIN_LEN: usize = 128;
+const CALLS: usize = 100;
+
+/// Counts the allocations of every thread, so that the tests running in parallel do not interfere.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Number of allocations made by `f`.
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn signal() -> Vec<f32> {
+    (0..WIN_LEN)
+        .map(|i| ((i * 7919) % 127) as f32 / 127. - 0.5)
+        .collect()
+}
+
+#[test]
+fn test_into_does_not_allocate() {
+    let extractor: CepstrumExtractor<f32> =
+        CepstrumExtractor::new(WIN_LEN).with_lengths(&[WIN_LEN * 2]);
+    extractor.extend_instances(2);
+
+    let signal = signal();
+    let complex: Vec<Complex<f32>> = signal.iter().map(|s| Complex::from(*s)).collect();
+    let mut out = vec![0f32; WIN_LEN / 2];
+    let mut long_out = vec![0f32; WIN_LEN];
+    let long_signal = [signal.clone(), signal.clone()].concat();
+
+    // Warm up: the scratches of the instances are created.
+    extractor.rceps_into(&signal, &mut out);
+    extractor.rceps_with_instance_into(&signal, &mut out, 1);
+
+    let count = allocations(|| {
+        for _ in 0..CALLS {
+            extractor.rceps_into(&signal, &mut out);
+            extractor.cceps_into(&complex, &mut out);
+            extractor.rceps_into(&long_signal, &mut long_out);
+            extractor.rceps_with_instance_into(&signal, &mut out, 1);
+            extractor.cceps_with_instance_into(&signal, &mut out, 1);
+
+            let mut guard = extractor.acquire();
+            guard.rceps_into(&signal, &mut out);
+        }
+    });
+    assert_eq!(count, 0);
+
+    // Same results as the allocating methods.
+    let expected: Vec<f32> = extractor
+        .rceps_to_vec(&complex)
+        .iter()
+        .map(|c| c.re)
+        .collect();
+    extractor.rceps_into(&signal, &mut out);
+    assert_eq!(out, expected);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_thread_local_into_does_not_allocate() {
+    let extractor: CepstrumExtractor<f64> =
+        CepstrumExtractor::new(WIN_LEN).with_thread_local_scratches();
+
+    let signal: Vec<f64> = signal().iter().map(|s| *s as f64).collect();
+    let mut out = vec![0f64; WIN_LEN / 2];
+
+    // Warm up: the scratch of this thread is created.
+    extractor.rceps_into(&signal, &mut out);
+
+    let count = allocations(|| {
+        for _ in 0..CALLS {
+            extractor.rceps_into(&signal, &mut out);
+            extractor.cceps_into(&signal, &mut out);
+        }
+    });
+    assert_eq!(count, 0);
+}
@@ -6,6 +6,7 @@ use std::{fs, thread};
 use rayon::iter::ParallelIterator;
 use rayon::prelude::{IndexedParallelIterator, ParallelSlice};
 
+use cepstrum_extractor::num_complex::Complex;
 use cepstrum_extractor::{CepstrumExtractor, RealToComplex};
 
 const SHIFT: usize = 20;
@@ -160,3 +161,186 @@ fn test_multi_thread_rayon() {
             assert_eq!(conv[..conv.len() / 2], out[i]);
         });
 }
+
+#[test]
+fn test_cross_cepstrum() {
+    let signal: Vec<f64> = (0..WIN_LEN)
+        .map(|i| ((i * 7919) % 127) as f64 / 127. - 0.5)
+        .collect();
+    let signal = signal.to_complex_vec();
+
+    let extractor = CepstrumExtractor::new(WIN_LEN);
+
+    // The cross-cepstrum of a signal with itself is the cepstrum of its power spectrum.
+    let rceps: Vec<Complex<f64>> = extractor.rceps_to_vec(&signal);
+    let rxceps = extractor.rxceps_to_vec(&signal, &signal);
+    let cxceps = extractor.cxceps_to_vec(&signal, &signal);
+
+    for ((r, rx), cx) in rceps.iter().zip(rxceps.iter()).zip(cxceps.iter()) {
+        assert!((r * 2. - rx).norm() < 1e-9);
+        assert!((rx - cx).norm() < 1e-9);
+    }
+}
+
+#[test]
+fn test_cross_cepstrum_delay() {
+    const DELAY: usize = 20;
+    const GAIN: f64 = 0.6;
+
+    // A minimum-phase pulse, and the same pulse with a copy delayed by `DELAY` samples: their
+    // cross-spectrum is the power spectrum of the pulse times `1 + GAIN * e^(-iωDELAY)`, whose
+    // phase is not zero.
+    let pulse: Vec<f64> = (0..WIN_LEN).map(|i| 0.5f64.powi(i as i32)).collect();
+    let echoed: Vec<f64> = (0..WIN_LEN)
+        .map(|i| pulse[i] + GAIN * pulse[(i + WIN_LEN - DELAY) % WIN_LEN])
+        .collect();
+    let (pulse, echoed) = (pulse.to_complex_vec(), echoed.to_complex_vec());
+
+    let extractor = CepstrumExtractor::new(WIN_LEN);
+
+    // What the pulse contributes is removed by subtracting its cross-cepstrum with itself; the
+    // inverse transform is not normalised.
+    type Cross = fn(&CepstrumExtractor<f64>, &[Complex<f64>], &[Complex<f64>]) -> Vec<Complex<f64>>;
+    let echo = |cross: Cross| {
+        let own = cross(&extractor, &pulse, &pulse);
+        cross(&extractor, &echoed, &pulse)
+            .iter()
+            .zip(own)
+            .map(|(c, o)| (c - o).re / WIN_LEN as f64)
+            .collect::<Vec<f64>>()
+    };
+    let peak = |ceps: &[f64]| {
+        (1..ceps.len())
+            .max_by(|a, b| ceps[*a].partial_cmp(&ceps[*b]).unwrap())
+            .unwrap()
+    };
+
+    // The real cross-cepstrum splits the echo between the positive and negative quefrencies...
+    let real = echo(CepstrumExtractor::rxceps_to_vec);
+    assert_eq!(peak(&real), DELAY);
+    assert!((real[DELAY] - GAIN / 2.).abs() < 1e-6, "{}", real[DELAY]);
+
+    // ...while the complex one, keeping the phase, places all of it at the delay.
+    let complex = echo(CepstrumExtractor::cxceps_to_vec);
+    assert_eq!(peak(&complex), DELAY);
+    assert!((complex[DELAY] - GAIN).abs() < 1e-6, "{}", complex[DELAY]);
+    assert!((complex[DELAY * 2] + GAIN * GAIN / 2.).abs() < 1e-6);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_multi_thread_guard() {
+    let signal: Vec<f32> = (0..WIN_LEN * 40)
+        .map(|i| ((i * 7919) % 127) as f32 / 127. - 0.5)
+        .collect();
+
+    // A single instance: the others are added by `acquire` as needed.
+    let extractor: Arc<CepstrumExtractor<f32>> = Arc::new(CepstrumExtractor::new(WIN_LEN));
+
+    // A local pool, since `test_multi_thread_rayon` builds the global one.
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(THREADS)
+        .build()
+        .unwrap();
+    let out: Vec<Vec<Complex<f32>>> = pool.install(|| {
+        signal
+            .par_chunks_exact(WIN_LEN)
+            .map(|chunk| extractor.acquire().rceps_to_vec(&chunk.to_complex_vec()))
+            .collect()
+    });
+
+    signal
+        .chunks_exact(WIN_LEN)
+        .enumerate()
+        .for_each(|(i, chunk)| {
+            assert_eq!(extractor.rceps_to_vec(&chunk.to_complex_vec()), out[i]);
+        });
+}
+
+#[test]
+#[cfg(feature = "std")]
+#[cfg_attr(miri, ignore)]
+fn test_thread_local_scratches() {
+    let signal: Vec<f32> = (0..WIN_LEN * 40)
+        .map(|i| ((i * 7919) % 127) as f32 / 127. - 0.5)
+        .collect();
+
+    let extractor: Arc<CepstrumExtractor<f32>> =
+        Arc::new(CepstrumExtractor::new(WIN_LEN).with_thread_local_scratches());
+    let expected: Vec<Vec<Complex<f32>>> = signal
+        .chunks_exact(WIN_LEN)
+        .map(|chunk| extractor.rceps_to_vec(&chunk.to_complex_vec()))
+        .collect();
+
+    // Plain threads and Rayon pools, running at the same time, without any index.
+    thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|| {
+                let out: Vec<Vec<Complex<f32>>> = signal
+                    .chunks_exact(WIN_LEN)
+                    .map(|chunk| extractor.rceps_to_vec(&chunk.to_complex_vec()))
+                    .collect();
+                assert_eq!(out, expected);
+            });
+        }
+
+        // Nested local pools, since `test_multi_thread_rayon` builds the global one.
+        let outer = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let inner = rayon::ThreadPoolBuilder::new().num_threads(3).build().unwrap();
+        outer.install(|| {
+            rayon::join(
+                || {
+                    inner.install(|| {
+                        let out: Vec<Vec<Complex<f32>>> = signal
+                            .par_chunks_exact(WIN_LEN)
+                            .map(|chunk| extractor.rceps_to_vec(&chunk.to_complex_vec()))
+                            .collect();
+                        assert_eq!(out, expected);
+                    })
+                },
+                || {
+                    let out: Vec<Vec<Complex<f32>>> = signal
+                        .par_chunks_exact(WIN_LEN)
+                        .map(|chunk| extractor.rceps_to_vec(&chunk.to_complex_vec()))
+                        .collect();
+                    assert_eq!(out, expected);
+                },
+            )
+        });
+    });
+}
+
+#[test]
+fn test_set_len_while_shared() {
+    const LENS: [usize; 2] = [WIN_LEN, WIN_LEN * 2];
+
+    let signal: Vec<Complex<f32>> = (0..LENS[1])
+        .map(|i| ((i * 7919) % 127) as f32 / 127. - 0.5)
+        .collect::<Vec<f32>>()
+        .to_complex_vec();
+    let expected: Vec<Vec<Complex<f32>>> = LENS
+        .iter()
+        .map(|len| CepstrumExtractor::new(*len).rceps_to_vec(&signal[..*len]))
+        .collect();
+
+    let extractor: Arc<CepstrumExtractor<f32>> = Arc::new(CepstrumExtractor::new(LENS[0]));
+
+    // Every guard computes with the length the extractor had when it was created.
+    thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|| {
+                for _ in 0..200 {
+                    let mut instance = extractor.acquire();
+                    let len = instance.win_len();
+                    let ceps = instance.rceps_to_vec(&signal[..len]);
+
+                    assert_eq!(ceps, expected[LENS.iter().position(|l| *l == len).unwrap()]);
+                }
+            });
+        }
+
+        for i in 0..200 {
+            extractor.set_len(LENS[i % 2]);
+        }
+    });
+}